@@ -0,0 +1,307 @@
+use crate::math::matrix::{add, identity, inverse3, multiply, skew, subtract, transpose, Matrix};
+use crate::math::{Quaternion, Vec3};
+
+const GRAVITY: f32 = 9.80665;
+
+/// which way up is, in the world frame
+fn up() -> Vec3 {
+    Vec3::new(1.0, 0.0, 0.0)
+}
+
+/// how the gyro and accelerometer are put together
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AttitudeFilter {
+    /// just integrates the gyro, so it drifts with whatever bias is left after calibrating
+    Gyro,
+    /// complementary (mahony) filter, pulling the estimate towards whatever the accelerometer says is up. `kp` is how
+    /// hard it pulls, `ki` slowly soaks up the gyro's bias
+    Mahony { kp: f32, ki: f32 },
+    /// multiplicative extended kalman filter, which also estimates the gyro's bias. the noises are standard
+    /// deviations: gyro (rad/s), bias wander (rad/s per sqrt(second)) and the accelerometer's direction (fraction of g)
+    Mekf {
+        gyro_noise: f32,
+        bias_noise: f32,
+        accel_noise: f32,
+    },
+}
+
+impl AttitudeFilter {
+    pub fn mahony() -> Self {
+        Self::Mahony { kp: 1.0, ki: 0.05 }
+    }
+
+    pub fn mekf() -> Self {
+        Self::Mekf {
+            gyro_noise: 0.003,
+            bias_noise: 0.0002,
+            accel_noise: 0.05,
+        }
+    }
+}
+
+/**
+ * works out which way the rocket is pointing from the imu, so the flight code doesn't get to peek at the truth
+ *
+ * sitting on the pad it calibrates (averaging the gyro for its bias, and the accelerometer for which way is down),
+ * then in flight it integrates the gyro. the accelerometer only knows where down is when nothing but the ground (or
+ * the rail) is pushing on the rocket, so it is ignored whenever it doesn't read about 1g (like the whole boost)
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct AttitudeEstimator {
+    filter: AttitudeFilter,
+    /// body to world, same as PhysicsBody's
+    rotation: Quaternion,
+    gyro_bias: Vec3,
+    // mahony's integral term
+    integral: Vec3,
+    // mekf's covariance of [attitude error, gyro bias]
+    p: Matrix<6, 6>,
+    // how far from 1g (as a fraction) the accelerometer can read and still be trusted
+    accel_tolerance: f32,
+    // sums of the readings on the pad
+    calibration_gyro: Vec3,
+    calibration_accel: Vec3,
+    calibration_samples: u32,
+}
+
+impl AttitudeEstimator {
+    pub fn new(filter: AttitudeFilter) -> Self {
+        Self {
+            filter,
+            rotation: Quaternion::default(),
+            gyro_bias: Vec3::default(),
+            integral: Vec3::default(),
+            p: initial_covariance(0.1, 0.01),
+            accel_tolerance: 0.1,
+            calibration_gyro: Vec3::default(),
+            calibration_accel: Vec3::default(),
+            calibration_samples: 0,
+        }
+    }
+
+    /// where the estimate starts, before calibrating. the accelerometer can't tell which way the rocket is rolled,
+    /// so that is kept from here
+    pub fn rotation(mut self, rotation: Quaternion) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    /// how far from 1g (as a fraction) the accelerometer can read and still be used to find up
+    pub fn accel_tolerance(mut self, tolerance: f32) -> Self {
+        self.accel_tolerance = tolerance;
+        self
+    }
+
+    /// the estimated rotation, body to world
+    pub fn estimate(&self) -> Quaternion {
+        self.rotation
+    }
+
+    /// rad/s
+    pub fn gyro_bias(&self) -> Vec3 {
+        self.gyro_bias
+    }
+
+    pub fn filter(&self) -> AttitudeFilter {
+        self.filter
+    }
+
+    /// adds a reading taken sitting still on the pad, which are averaged when the estimator is next updated
+    pub fn calibrate(&mut self, gyro: Vec3, accel: Vec3) {
+        self.calibration_gyro += gyro;
+        self.calibration_accel += accel;
+        self.calibration_samples += 1;
+    }
+
+    /// uses the readings from the pad, this is done by the first update after calibrating
+    pub fn finish_calibration(&mut self) {
+        if self.calibration_samples == 0 {
+            return;
+        }
+        let samples = self.calibration_samples as f32;
+        self.gyro_bias = self.calibration_gyro / samples;
+        let down = self.rotation.rotate(self.calibration_accel / samples);
+        self.rotation = (Quaternion::between(down, up()) * self.rotation).normalize();
+        self.integral = Vec3::default();
+        self.p = initial_covariance(0.01, 0.001);
+
+        self.calibration_gyro = Vec3::default();
+        self.calibration_accel = Vec3::default();
+        self.calibration_samples = 0;
+    }
+
+    /// if the accelerometer is only feeling the ground holding the rocket up, so knows which way is up
+    pub fn trusts_accel(&self, accel: Vec3) -> bool {
+        (accel.norm() - GRAVITY).abs() < self.accel_tolerance * GRAVITY
+    }
+
+    /// moves the estimate along `dt` secs with an imu reading (rad/s and m/s^2, body frame)
+    pub fn update(&mut self, gyro: Vec3, accel: Vec3, dt: f32) {
+        self.finish_calibration();
+        let trusted = self.trusts_accel(accel);
+        let mut rate = gyro - self.gyro_bias;
+
+        match self.filter {
+            AttitudeFilter::Gyro => self.integrate(rate, dt),
+            AttitudeFilter::Mahony { kp, ki } => {
+                if trusted {
+                    // turns the estimate so the up it expects lines up with the up it feels
+                    let error = accel.normalize().cross(self.rotation.conj().rotate(up()));
+                    self.integral += error * ki * dt;
+                    rate += error * kp;
+                }
+                self.integrate(rate + self.integral, dt);
+            }
+            AttitudeFilter::Mekf {
+                gyro_noise,
+                bias_noise,
+                accel_noise,
+            } => {
+                self.integrate(rate, dt);
+                self.propagate_covariance(rate, dt, gyro_noise, bias_noise);
+                if trusted {
+                    self.correct(accel, accel_noise);
+                }
+            }
+        }
+    }
+
+    fn integrate(&mut self, rate: Vec3, dt: f32) {
+        let angle = rate.norm() * dt;
+        if angle > 0.0 {
+            self.rotation =
+                (self.rotation * Quaternion::from_axis_angle(rate.normalize(), angle)).normalize();
+        }
+    }
+
+    fn propagate_covariance(&mut self, rate: Vec3, dt: f32, gyro_noise: f32, bias_noise: f32) {
+        // the attitude error is turned by the rate, and grows with any bias left over
+        let mut f = identity::<6>();
+        let turn = skew(rate);
+        for i in 0..3 {
+            for j in 0..3 {
+                f[i][j] -= turn[i][j] * dt;
+            }
+            f[i][i + 3] = -dt;
+        }
+        let mut q = [[0.0; 6]; 6];
+        for i in 0..3 {
+            q[i][i] = gyro_noise.powi(2) * dt;
+            q[i + 3][i + 3] = bias_noise.powi(2) * dt;
+        }
+        self.p = add(multiply(multiply(f, self.p), transpose(f)), q);
+    }
+
+    fn correct(&mut self, accel: Vec3, accel_noise: f32) {
+        let expected = self.rotation.conj().rotate(up());
+        let measured = accel.normalize();
+
+        let mut h = [[0.0; 6]; 3];
+        for (h_row, skew_row) in h.iter_mut().zip(skew(expected)) {
+            h_row[..3].copy_from_slice(&skew_row);
+        }
+        let pht = multiply(self.p, transpose(h));
+        let s = add(
+            multiply(h, pht),
+            identity::<3>().map(|row| row.map(|v| v * accel_noise.powi(2))),
+        );
+        let Some(s_inverse) = inverse3(s) else {
+            return;
+        };
+        let gain = multiply(pht, s_inverse);
+
+        let innovation = measured - expected;
+        let correction = multiply(gain, [[innovation.x], [innovation.y], [innovation.z]]);
+        let correction = |i: usize| correction[i][0];
+
+        let half_angle = Vec3::new(correction(0), correction(1), correction(2)) * 0.5;
+        self.rotation = (self.rotation
+            * Quaternion::new(1.0, half_angle.x, half_angle.y, half_angle.z))
+        .normalize();
+        self.gyro_bias += Vec3::new(correction(3), correction(4), correction(5));
+        self.p = subtract(self.p, multiply(multiply(gain, h), self.p));
+    }
+}
+
+/// mekf covariance for an attitude (rad) and gyro bias (rad/s) uncertainty
+fn initial_covariance(attitude: f32, bias: f32) -> Matrix<6, 6> {
+    let mut p = [[0.0; 6]; 6];
+    for i in 0..3 {
+        p[i][i] = attitude.powi(2);
+        p[i + 3][i + 3] = bias.powi(2);
+    }
+    p
+}
+
+/// what an imu sitting at `rotation` reads, with the gyro reading `bias`
+#[cfg(test)]
+fn still_imu(rotation: Quaternion, bias: Vec3) -> (Vec3, Vec3) {
+    (bias, rotation.conj().rotate(up() * GRAVITY))
+}
+
+#[test]
+fn test_calibration() {
+    let truth = Quaternion::from_axis_angle(Vec3::new(0.0, 1.0, 0.0), 5f32.to_radians());
+    let bias = Vec3::new(0.01, -0.02, 0.005);
+    let mut estimator = AttitudeEstimator::new(AttitudeFilter::Gyro);
+    let (gyro, accel) = still_imu(truth, bias);
+    for _ in 0..100 {
+        estimator.calibrate(gyro, accel);
+    }
+    estimator.update(gyro, accel, 0.01);
+    assert!((estimator.gyro_bias() - bias).norm() < 1e-6);
+    assert!(estimator.estimate().angle_to(truth) < 1e-3);
+}
+
+#[test]
+fn test_accel_correction() {
+    use crate::math::gaussian;
+    use rand::SeedableRng;
+    let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+
+    let truth = Quaternion::from_axis_angle(Vec3::new(0.0, 0.6, 0.8), 10f32.to_radians());
+    let bias = Vec3::new(0.0, 0.01, 0.0);
+    for filter in [AttitudeFilter::mahony(), AttitudeFilter::mekf()] {
+        // starts off upright, without calibrating
+        let mut estimator = AttitudeEstimator::new(filter);
+        for _ in 0..6000 {
+            let (gyro, accel) = still_imu(truth, bias);
+            let noise = Vec3::new(
+                gaussian(&mut rng, 0.1),
+                gaussian(&mut rng, 0.1),
+                gaussian(&mut rng, 0.1),
+            );
+            estimator.update(gyro, accel + noise, 0.01);
+        }
+        // it can only find the tilt, not which way the rocket is turned about up
+        let tilt = estimator
+            .estimate()
+            .conj()
+            .rotate(up())
+            .angle_between(truth.conj().rotate(up()));
+        assert!(tilt < 1f32.to_radians());
+        if let AttitudeFilter::Mekf { .. } = filter {
+            // a bias about a sideways axis shows up as the rocket tipping over
+            assert!((estimator.gyro_bias().y - bias.y).abs() < 0.002);
+        }
+    }
+}
+
+#[test]
+fn test_boost() {
+    // turning while the motor pushes hard, the accelerometer is no help
+    let rate = Vec3::new(0.0, 0.2, 0.0);
+    for filter in [
+        AttitudeFilter::Gyro,
+        AttitudeFilter::mahony(),
+        AttitudeFilter::mekf(),
+    ] {
+        let mut truth = Quaternion::default();
+        let mut estimator = AttitudeEstimator::new(filter);
+        for _ in 0..200 {
+            truth *= Quaternion::from_axis_angle(rate.normalize(), rate.norm() * 0.01);
+            estimator.update(rate, Vec3::new(5.0 * GRAVITY, 0.0, 0.0), 0.01);
+        }
+        assert!(estimator.estimate().angle_to(truth) < 1e-3);
+    }
+}
@@ -1,6 +1,7 @@
 /// Full State Feedback
 ///
 /// (pid but better and more computationaly intensive)
+#[derive(Debug, Clone, PartialEq)]
 pub struct FSF {
     gain_a: f32,
     gain_b: f32,
@@ -1,64 +1,224 @@
-// // TODO fix dis, as in the words of ZegesMended: its not how you do a kalman
-// pub struct Kalman {
-//     p: [f32; 4],
-//     q: f32,
-//     r: f32,
-//     vel: f32,
-//     pos: f32,
-
-// }
-
-// impl Kalman {
-//     pub fn new() -> Self {
-//         Self {
-//             p: [0.0, 0.0, 0.0, 0.0],
-//             vel: 0.0,
-//             pos: 0.0,
-//             q: 0.0,
-//             r: 0.0,
-//         }
-//     }
-
-//     pub fn update_accel(self, accel, dt) -> None:
-
-//         self.pos += self.vel * dt + 0.5 * accel * (dt * dt)
-//         self.vel += accel * dt
-        
-//         q_dtdt = self.Q * (dt * dt)
-//         self.P[0] += (self.P[2] + self.P[1] +
-//                     (self.P[3] + q_dtdt * 0.25) * dt) * dt
-//         self.P[1] += (self.P[3] + q_dtdt * 0.5) * dt
-//         self.P[2] = self.P[1]
-//         self.P[3] += q_dtdt
-
-//     pub fn update_position(self, pos) -> None:
-
-//         y = self.pos - pos
-
-//         inv = 1.0 / (self.P[0] + self.R)
-
-//         K_a = self.P[0] * inv
-//         K_b = self.P[2] * inv
-
-//         self.pos += K_a * y
-//         self.vel += K_b * self.vel
-
-//         self.vel += y*10
-//         self.vel /= 11.0
-
-//         self.pos += pos
-//         self.pos /= 2.0
-
-//         self.P[0] -= K_a * self.P[0]
-//         self.P[1] -= K_a * self.P[1]
-//         self.P[2] -= K_b * self.P[0]
-//         self.P[3] -= K_b * self.P[1]
-
-//     pub fn get_position(self) -> float:
-
-//         return self.pos
-
-//     pub fn get_velocity(self) -> float:
-
-//         return self.vel
-// }
\ No newline at end of file
+use std::io::Write;
+
+use crate::math::matrix::{multiply, transpose};
+
+/**
+ * altitude and vertical velocity from the accelerometer and barometer
+ *
+ * the accelerometer is good over short times but drifts (it has a bias, and any error in it is integrated twice), the
+ * barometer doesn't drift but is noisy and slow. so the accelerometer moves the estimate along between barometer
+ * readings, and the barometer pulls it back (and works out the accelerometer's bias while it's at it)
+ *
+ * the state is [position, velocity, accelerometer bias], all along the vertical
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct Kalman {
+    state: [f32; 3],
+    // covariance of the state
+    p: [[f32; 3]; 3],
+    // process noise (Q), standard deviations
+    // how far off the acceleration can be from what the accelerometer says, m/s^2
+    accel_noise: f32,
+    // how fast the accelerometer's bias can wander, m/s^2 per sqrt(second)
+    bias_noise: f32,
+    // measurement noise (R), standard deviation of the altitude readings, m
+    altitude_noise: f32,
+}
+
+impl Default for Kalman {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Kalman {
+    /// starts at 0 (on the pad, sitting still) with no bias
+    pub fn new() -> Self {
+        Self {
+            state: [0.0; 3],
+            p: [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]],
+            accel_noise: 0.5,
+            bias_noise: 0.01,
+            altitude_noise: 1.0,
+        }
+    }
+
+    /// how far off the acceleration can be from what the accelerometer says (Q), m/s^2
+    pub fn accel_noise(mut self, noise: f32) -> Self {
+        self.accel_noise = noise;
+        self
+    }
+
+    /// how fast the accelerometer's bias can wander (Q), m/s^2 per sqrt(second)
+    pub fn bias_noise(mut self, noise: f32) -> Self {
+        self.bias_noise = noise;
+        self
+    }
+
+    /// how noisy the altitude readings are (R), m
+    pub fn altitude_noise(mut self, noise: f32) -> Self {
+        self.altitude_noise = noise;
+        self
+    }
+
+    /// how unsure the filter starts about its position (m), velocity (m/s) and bias (m/s^2)
+    pub fn initial_uncertainty(mut self, position: f32, velocity: f32, bias: f32) -> Self {
+        self.p = [[position.powi(2), 0.0, 0.0], [0.0, velocity.powi(2), 0.0], [0.0, 0.0, bias.powi(2)]];
+        self
+    }
+
+    /// m, up
+    pub fn position(&self) -> f32 {
+        self.state[0]
+    }
+
+    /// m/s, up
+    pub fn velocity(&self) -> f32 {
+        self.state[1]
+    }
+
+    /// what the filter thinks the accelerometer's bias is, m/s^2
+    pub fn accel_bias(&self) -> f32 {
+        self.state[2]
+    }
+
+    /// covariance of [position, velocity, accelerometer bias]
+    pub fn covariance(&self) -> [[f32; 3]; 3] {
+        self.p
+    }
+
+    /// moves the estimate along `dt` secs with the measured vertical acceleration (gravity taken out, so 0 sitting
+    /// on the pad)
+    pub fn predict(&mut self, accel: f32, dt: f32) {
+        let [position, velocity, bias] = self.state;
+        let accel = accel - bias;
+        self.state = [position + velocity * dt + 0.5 * accel * dt * dt, velocity + accel * dt, bias];
+
+        let f = [[1.0, dt, -0.5 * dt * dt], [0.0, 1.0, -dt], [0.0, 0.0, 1.0]];
+        self.p = multiply(multiply(f, self.p), transpose(f));
+
+        // the acceleration error goes into the position and velocity, the bias wanders on its own
+        let g = [0.5 * dt * dt, dt];
+        let accel_variance = self.accel_noise.powi(2);
+        for (row, g_i) in self.p.iter_mut().zip(g) {
+            for (value, g_j) in row.iter_mut().zip(g) {
+                *value += g_i * g_j * accel_variance;
+            }
+        }
+        self.p[2][2] += self.bias_noise.powi(2) * dt;
+    }
+
+    /// corrects the estimate with a measured altitude (m, from the barometer)
+    pub fn update_altitude(&mut self, altitude: f32) {
+        let innovation = altitude - self.state[0];
+        let s = self.p[0][0] + self.altitude_noise.powi(2);
+        let gain = [self.p[0][0] / s, self.p[1][0] / s, self.p[2][0] / s];
+
+        for (state, gain) in self.state.iter_mut().zip(gain) {
+            *state += gain * innovation;
+        }
+        // P = (I - K H) P
+        let first_row = self.p[0];
+        for (row, gain) in self.p.iter_mut().zip(gain) {
+            for (value, first) in row.iter_mut().zip(first_row) {
+                *value -= gain * first;
+            }
+        }
+    }
+}
+
+/// what the filter thought against what was really happening, at one time
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EstimationError {
+    pub time: f32,
+    pub position: f32,
+    pub velocity: f32,
+    pub true_position: f32,
+    pub true_velocity: f32,
+}
+
+impl EstimationError {
+    pub fn position_error(&self) -> f32 {
+        self.position - self.true_position
+    }
+
+    pub fn velocity_error(&self) -> f32 {
+        self.velocity - self.true_velocity
+    }
+
+    /// writes every error out as a csv, for plotting
+    pub fn write_csv(errors: &[Self], writer: impl Write) -> Result<(), csv::Error> {
+        let mut writer = csv::Writer::from_writer(writer);
+        writer.write_record([
+            "time",
+            "position",
+            "velocity",
+            "true_position",
+            "true_velocity",
+            "position_error",
+            "velocity_error",
+        ])?;
+        for error in errors {
+            writer.write_record(
+                [
+                    error.time,
+                    error.position,
+                    error.velocity,
+                    error.true_position,
+                    error.true_velocity,
+                    error.position_error(),
+                    error.velocity_error(),
+                ]
+                .map(|value| value.to_string()),
+            )?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+#[test]
+fn test_kalman() {
+    use crate::math::gaussian;
+    use rand::SeedableRng;
+    let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+
+    let mut kalman = Kalman::new().accel_noise(0.1).altitude_noise(2.0);
+    let dt = 0.01;
+    let (accel, bias) = (5.0, 0.3);
+    let mut error = 0.0f32;
+    for step in 1..=3000 {
+        let time = step as f32 * dt;
+        kalman.predict(accel + bias + gaussian(&mut rng, 0.05), dt);
+        // baro at 20hz
+        if step % 5 == 0 {
+            kalman.update_altitude(0.5 * accel * time * time + gaussian(&mut rng, 2.0));
+        }
+        if step > 500 {
+            error = error.max((kalman.position() - 0.5 * accel * time * time).abs());
+        }
+    }
+    // much better than the altitude readings alone, and it found the bias
+    assert!(error < 2.0);
+    assert!((kalman.velocity() - accel * 30.0).abs() < 0.5);
+    assert!((kalman.accel_bias() - bias).abs() < 0.1);
+    // and it knows its position better than the barometer does
+    assert!(kalman.covariance()[0][0] < 4.0);
+}
+
+#[test]
+fn test_estimation_csv() {
+    let errors = [EstimationError {
+        time: 1.0,
+        position: 10.5,
+        velocity: 2.0,
+        true_position: 10.0,
+        true_velocity: 2.5,
+    }];
+    let mut out = vec![];
+    EstimationError::write_csv(&errors, &mut out).unwrap();
+    let out = String::from_utf8(out).unwrap();
+    let mut lines = out.lines();
+    assert_eq!(lines.next(), Some("time,position,velocity,true_position,true_velocity,position_error,velocity_error"));
+    assert_eq!(lines.next(), Some("1,10.5,2,10,2.5,0.5,-0.5"));
+}
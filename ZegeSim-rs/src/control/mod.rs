@@ -1,9 +1,13 @@
+pub mod attitude;
 pub mod fsf;
+pub mod kalman;
 pub mod pid;
+pub mod reaction_wheel;
 pub mod tvc;
-// FIXME completely unnecessary apparently (mabey later)
-// pub mod kalman;
 
+pub use attitude::{AttitudeEstimator, AttitudeFilter};
 pub use fsf::FSF;
+pub use kalman::{EstimationError, Kalman};
 pub use pid::PID;
+pub use reaction_wheel::ReactionWheel;
 pub use tvc::TVC;
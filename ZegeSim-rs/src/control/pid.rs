@@ -63,6 +63,7 @@ impl PID {
         self.i = 0.0;
     }
 
+    #[allow(clippy::if_same_then_else)]
     pub fn compute(&mut self, process: f32, dt: Duration) {
         let dt = dt.as_secs_f32();
         let change = process - self.last_process;
@@ -0,0 +1,115 @@
+use crate::math::{clamp, Vec3};
+
+/**
+ * a wheel spun by a motor along the rocket's roll axis, so the rocket can be rolled by spinning the wheel the other way
+ *
+ * the motor can only push so hard (`max_torque`), and once the wheel is spinning as fast as it can (`max_speed`) it
+ * can't push it any faster, so a wheel that has soaked up too much roll can only roll the rocket back the other way.
+ * the wheel's momentum also fights the rocket pitching and yawing (gyroscopic coupling)
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReactionWheel {
+    /// of the wheel about its axle, kg*m^2
+    inertia: f32,
+    /// n*m
+    max_torque: f32,
+    /// rad/s
+    max_speed: f32,
+    /// rad/s, relative to the rocket
+    speed: f32,
+    /// what the motor actually pushed the wheel with last update, n*m
+    torque: f32,
+}
+
+impl ReactionWheel {
+    /// `inertia` in kg*m^2, `max_torque` in n*m and `max_speed` in rad/s
+    pub fn new(inertia: f32, max_torque: f32, max_speed: f32) -> Self {
+        Self {
+            inertia,
+            max_torque,
+            max_speed,
+            speed: 0.0,
+            torque: 0.0,
+        }
+    }
+
+    /// how fast the wheel is spinning relative to the rocket, rad/s
+    pub fn speed(&self) -> f32 {
+        self.speed
+    }
+
+    /// the wheel's angular momentum, kg*m^2/s, body frame
+    pub fn momentum(&self) -> Vec3 {
+        Vec3::new(self.inertia * self.speed, 0.0, 0.0)
+    }
+
+    /// the torque the motor last put on the rocket about the roll axis, n*m. less than asked for if the motor or wheel
+    /// is at its limit
+    pub fn torque(&self) -> f32 {
+        self.torque
+    }
+
+    /// if the wheel can't spin any faster
+    pub fn saturated(&self) -> bool {
+        self.speed.abs() >= self.max_speed
+    }
+
+    /// spins the wheel for `dt` secs, trying to put `command` n*m on the rocket about its roll axis. returns the torque
+    /// on the rocket (n*m, body frame), including the wheel's momentum being turned by `rotational_velocity` (rad/s,
+    /// body frame)
+    pub fn update(&mut self, command: f32, rotational_velocity: Vec3, dt: f32) -> Vec3 {
+        if dt <= 0.0 || self.inertia <= 0.0 {
+            return Vec3::default();
+        }
+        // the motor pushes the wheel one way, and the rocket the other
+        let wheel_torque = clamp(-command, -self.max_torque, self.max_torque);
+        let speed = clamp(
+            self.speed + wheel_torque / self.inertia * dt,
+            -self.max_speed,
+            self.max_speed,
+        );
+        // what the wheel really took, it can't go past its top speed
+        self.torque = -(speed - self.speed) * self.inertia / dt;
+        self.speed = speed;
+
+        Vec3::new(self.torque, 0.0, 0.0) - rotational_velocity.cross(self.momentum())
+    }
+}
+
+#[test]
+fn test_reaction_wheel() {
+    let mut wheel = ReactionWheel::new(0.001, 0.05, 100.0);
+
+    // asking for more than the motor can do
+    let torque = wheel.update(0.1, Vec3::default(), 0.01);
+    assert!((torque.x - 0.05).abs() < 1e-5);
+    assert!((wheel.torque() - 0.05).abs() < 1e-5);
+    // spinning the other way to the rocket
+    assert!((wheel.speed() + 0.5).abs() < 1e-4);
+
+    // until it can't spin any faster
+    for _ in 0..1000 {
+        wheel.update(0.1, Vec3::default(), 0.01);
+    }
+    assert!(wheel.saturated());
+    assert_eq!(wheel.speed(), -100.0);
+    assert_eq!(wheel.update(0.1, Vec3::default(), 0.01), Vec3::default());
+    // but it can still slow down
+    assert!(wheel.update(-0.01, Vec3::default(), 0.01).x < 0.0);
+    assert!(!wheel.saturated());
+}
+
+#[test]
+fn test_gyroscopic_coupling() {
+    let mut wheel = ReactionWheel::new(0.001, 0.05, 100.0);
+    for _ in 0..100 {
+        wheel.update(-0.05, Vec3::default(), 0.01);
+    }
+    assert!((wheel.momentum().x - 0.05).abs() < 1e-5);
+
+    // pitching with the wheel spinning pushes the rocket about yaw
+    let torque = wheel.update(0.0, Vec3::new(0.0, 1.0, 0.0), 0.01);
+    assert_eq!(torque.x, 0.0);
+    assert_eq!(torque.y, 0.0);
+    assert!((torque.z - 0.05).abs() < 1e-5);
+}
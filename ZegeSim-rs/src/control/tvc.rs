@@ -1,3 +1,4 @@
+use crate::data::Settings;
 use crate::math::{clamp, Vec3, DEG_TO_RAD, RAD_TO_DEG};
 
 #[derive(Debug, Default, Clone)]
@@ -23,27 +24,44 @@ pub struct TVC {
 }
 
 impl TVC {
+    /// a tvc mount that does not move, with the servos linked straight to the mount
     pub fn new() -> Self {
-        Self::default()
+        Self {
+            // a ratio of 0 divides by zero in actuate
+            linkage_ratio: 1.0,
+            ..Self::default()
+        }
+    }
+
+    /// the mount from the settings, moving up to `max_tvc` degrees about y and z with servos turning at
+    /// `tvc_servo_speed` degrees a second
+    pub fn from_settings(settings: &Settings) -> Self {
+        let max = settings.max_tvc * settings.linkage_ratio;
+        Self {
+            min: -max,
+            max,
+            servo_speed: settings.tvc_servo_speed,
+            linkage_ratio: settings.linkage_ratio,
+            ..Self::default()
+        }
+    }
+
+    /// where the mount actually is (rad), which lags the command while the servos move
+    pub fn position(&self) -> Vec3 {
+        self.position
     }
 
     pub fn actuate(&mut self, command_angles: Vec3, dt: f32) {
         self.command.y = command_angles.y * RAD_TO_DEG * self.linkage_ratio;
         self.command.z = command_angles.z * RAD_TO_DEG * self.linkage_ratio;
 
-        let actuation_y = clamp(
-            self.command.y - self.servo_position.y,
-            -self.servo_speed,
-            self.servo_speed,
-        );
-        let actuation_z = clamp(
-            self.command.z - self.servo_position.z,
-            -self.servo_speed,
-            self.servo_speed,
-        );
+        // the servos can only turn so far each step
+        let step = self.servo_speed * dt;
+        let actuation_y = clamp(self.command.y - self.servo_position.y, -step, step);
+        let actuation_z = clamp(self.command.z - self.servo_position.z, -step, step);
 
-        self.servo_position.y += actuation_y * dt;
-        self.servo_position.z += actuation_z * dt;
+        self.servo_position.y += actuation_y;
+        self.servo_position.z += actuation_z;
 
         self.servo_position.y = clamp(self.servo_position.y, self.min.y, self.max.y);
         self.servo_position.z = clamp(self.servo_position.z, self.min.z, self.max.z);
@@ -61,3 +79,36 @@ impl TVC {
             thrust * (self.position.y).cos() - (thrust - (thrust * (self.position.z).cos()));
     }
 }
+
+#[test]
+fn test_new() {
+    // a mount that can't move stays straight, rather than going NaN
+    let mut tvc = TVC::new();
+    tvc.actuate(Vec3::new(0.0, 5.0 * DEG_TO_RAD, -5.0 * DEG_TO_RAD), 0.01);
+    assert_eq!(tvc.position(), Vec3::default());
+    tvc.calculate_forces(10.0);
+    assert_eq!(tvc.force, Vec3::new(10.0, 0.0, 0.0));
+}
+
+#[test]
+fn test_servo_speed() {
+    let mut tvc = TVC {
+        min: Vec3::new(0.0, -20.0, -20.0),
+        max: Vec3::new(0.0, 20.0, 20.0),
+        servo_speed: 100.0,
+        linkage_ratio: 2.0,
+        ..TVC::default()
+    };
+    // 5 degrees on the mount is 10 on the servo, which takes a tenth of a second
+    let command = Vec3::new(0.0, 5.0 * DEG_TO_RAD, -20.0 * DEG_TO_RAD);
+    tvc.actuate(command, 0.05);
+    assert!((tvc.position().y - 2.5 * DEG_TO_RAD).abs() < 1e-5);
+    tvc.actuate(command, 0.05);
+    tvc.actuate(command, 0.05);
+    assert!((tvc.position().y - 5.0 * DEG_TO_RAD).abs() < 1e-5);
+    // and no further than the mount goes
+    for _ in 0..10 {
+        tvc.actuate(command, 0.05);
+    }
+    assert!((tvc.position().z + 10.0 * DEG_TO_RAD).abs() < 1e-5);
+}
@@ -1,11 +1,12 @@
-use std::{fs::OpenOptions, path::PathBuf};
+use std::path::PathBuf;
 
-use csv::ReaderBuilder;
+use csv::{ReaderBuilder, Trim};
 
 use crate::math::Vec3;
 
 #[derive(Debug, Default, Clone)]
 pub struct FlightPath {
+    #[allow(dead_code)]
     setpoint: [f32; 4],
     setpoints: Vec<[f32; 4]>,
     current_setpoint: Vec3,
@@ -13,7 +14,12 @@ pub struct FlightPath {
 
 impl FlightPath {
     pub fn from_file(path: PathBuf) -> Self {
-        let mut reader = ReaderBuilder::new().from_path(path).unwrap();
+        // time, then the x, y and z setpoints (degrees), with no header
+        let mut reader = ReaderBuilder::new()
+            .has_headers(false)
+            .trim(Trim::All)
+            .from_path(path)
+            .unwrap();
 
         let mut setpoints = vec![];
 
@@ -32,6 +38,7 @@ impl FlightPath {
         }
     }
 
+    #[allow(clippy::needless_return)]
     pub fn get_next_setpoint(&mut self, time: f32) -> Vec3 {
         let mut setpoint_last: [f32; 4] = Default::default();
         let mut setpoint_future: [f32; 4] = Default::default();
@@ -18,6 +18,7 @@ reaction_wheel_output - a torque in newton-meters representing the torque from a
 
 motor_fire - the name of the motor you want to fire, leave blank to not fire any motor
 */
+#[derive(Debug, Clone, Default, PartialEq)]
 pub struct ControllData {
     pub tvc_position: Vec3,
     pub reaction_wheel_output: f32,
@@ -2,14 +2,22 @@ use std::fs::OpenOptions;
 use std::io::Read;
 use std::path::PathBuf;
 
-use yaml_rust::YamlLoader;
+use yaml_rust::{Yaml, YamlLoader};
+
+use rocketengine::atmosphere::Atmosphere;
+use rocketengine::motor::{MotorCatalog, MotorData};
 
 use crate::math::Vec3;
-use crate::motor::spec::MotorSpec;
+
+/// yaml reads `500` as an integer, but it's just as good a number as `500.0`
+fn number(value: &Yaml) -> Option<f64> {
+    value.as_f64().or_else(|| value.as_i64().map(|value| value as f64))
+}
 
 #[derive(Clone, Debug)]
 pub struct Settings {
-    pub motors: Vec<(String, MotorSpec)>,
+    /// motor name, and the motor from the catalog
+    pub motors: Vec<(String, MotorData)>,
     pub max_ignition_delay: f32,
 
     pub time_step: f32,
@@ -27,6 +35,8 @@ pub struct Settings {
     pub drag_coeff: f32,
 
     pub wind_speed: Vec3,
+    /// launch site elevation, and the measured ground temperature and pressure if they are given
+    pub atmosphere: Atmosphere,
 
     pub tvc_noise: f32,
 
@@ -48,62 +58,75 @@ impl Settings {
 
         let mut motors = vec![];
         for point in config["motors"].to_owned().into_hash().unwrap() {
+            let motor = point.1.as_str().unwrap();
             motors.push((
                 point.0.as_str().unwrap().to_string(),
-                MotorSpec::parse_from_str(point.0.as_str().unwrap()).unwrap(),
+                MotorCatalog::builtin()
+                    .find(motor)
+                    .unwrap_or_else(|| panic!("Unknown motor {}", motor))
+                    .clone(),
             ));
         }
 
-        let time_step = config["timeStep"].as_f64().unwrap() as f32;
-        let simulation_time = config["simTime"].as_f64().unwrap() as f32;
+        let time_step = number(&config["timeStep"]).unwrap() as f32;
+        let simulation_time = number(&config["simTime"]).unwrap() as f32;
 
-        let imu_gyro_read_speed = config["gyroSpeed"].as_f64().unwrap() as f32;
-        let imu_accel_read_speed = config["accelSpeed"].as_f64().unwrap() as f32;
-        let gps_read_speed = config["gpsSpeed"].as_f64().unwrap() as f32;
-        let baro_read_speed = config["baroSpeed"].as_f64().unwrap() as f32;
+        let imu_gyro_read_speed = number(&config["gyroSpeed"]).unwrap() as f32;
+        let imu_accel_read_speed = number(&config["accelSpeed"]).unwrap() as f32;
+        let gps_read_speed = number(&config["gpsSpeed"]).unwrap() as f32;
+        let baro_read_speed = number(&config["baroSpeed"]).unwrap() as f32;
 
-        let max_ignition_delay = config["max_motor_ignition_delay"].as_f64().unwrap() as f32;
+        let max_ignition_delay = number(&config["max_motor_ignition_delay"]).unwrap() as f32;
 
-        let mass = config["rocket_mass"].as_f64().unwrap() as f32;
-        let drag_area = config["drag_area"].as_f64().unwrap() as f32;
-        let drag_coeff = config["drag_coeff"].as_f64().unwrap() as f32;
-        let tvc_noise = config["tvc_noise"].as_f64().unwrap() as f32;
-        let tvc_servo_speed = config["tvc_servo_speed"].as_f64().unwrap() as f32;
-        let linkage_ratio = config["tvc_linkage_ratio"].as_f64().unwrap() as f32;
+        let mass = number(&config["rocket_mass"]).unwrap() as f32;
+        let drag_area = number(&config["drag_area"]).unwrap() as f32;
+        let drag_coeff = number(&config["drag_coeff"]).unwrap() as f32;
+        let tvc_noise = number(&config["tvc_noise"]).unwrap() as f32;
+        let tvc_servo_speed = number(&config["tvc_servo_speed"]).unwrap() as f32;
+        let linkage_ratio = number(&config["tvc_linkage_ratio"]).unwrap() as f32;
 
         let max_tvc_raw = config["max_tvc_angle"].as_vec().unwrap().to_owned();
         let max_tvc = Vec3::new(
             0.0,
-            max_tvc_raw[0].as_f64().unwrap() as f32,
-            max_tvc_raw[1].as_f64().unwrap() as f32,
+            number(&max_tvc_raw[0]).unwrap() as f32,
+            number(&max_tvc_raw[1]).unwrap() as f32,
         );
 
         let wind_speed_raw = config["wind_speed"].as_vec().unwrap().to_owned();
         let wind_speed = Vec3::new(
-            wind_speed_raw[0].as_f64().unwrap() as f32,
-            wind_speed_raw[1].as_f64().unwrap() as f32,
-            wind_speed_raw[2].as_f64().unwrap() as f32,
+            number(&wind_speed_raw[0]).unwrap() as f32,
+            number(&wind_speed_raw[1]).unwrap() as f32,
+            number(&wind_speed_raw[2]).unwrap() as f32,
         );
 
+        let mut atmosphere = Atmosphere::standard().elevation(number(&config["launch_elevation"]).unwrap_or(0.0));
+        if let Some(temperature) = number(&config["ground_temperature"]) {
+            // celsius in the config
+            atmosphere = atmosphere.ground_temperature(temperature + 273.15);
+        }
+        if let Some(pressure) = number(&config["ground_pressure"]) {
+            atmosphere = atmosphere.ground_pressure(pressure);
+        }
+
         let mmoi_raw = config["mmoi"].as_vec().unwrap().to_owned();
         let mmoi = Vec3::new(
-            mmoi_raw[0].as_f64().unwrap() as f32,
-            mmoi_raw[1].as_f64().unwrap() as f32,
-            mmoi_raw[2].as_f64().unwrap() as f32,
+            number(&mmoi_raw[0]).unwrap() as f32,
+            number(&mmoi_raw[1]).unwrap() as f32,
+            number(&mmoi_raw[2]).unwrap() as f32,
         );
 
-        let tvc_location_raw = config["mmoi"].as_vec().unwrap().to_owned();
+        let tvc_location_raw = config["tvc_location"].as_vec().unwrap().to_owned();
         let tvc_location = Vec3::new(
-            tvc_location_raw[0].as_f64().unwrap() as f32,
-            tvc_location_raw[1].as_f64().unwrap() as f32,
-            tvc_location_raw[2].as_f64().unwrap() as f32,
+            number(&tvc_location_raw[0]).unwrap() as f32,
+            number(&tvc_location_raw[1]).unwrap() as f32,
+            number(&tvc_location_raw[2]).unwrap() as f32,
         );
 
-        let cp_location_raw = config["mmoi"].as_vec().unwrap().to_owned();
+        let cp_location_raw = config["cp_location"].as_vec().unwrap().to_owned();
         let cp_location = Vec3::new(
-            cp_location_raw[0].as_f64().unwrap() as f32,
-            cp_location_raw[1].as_f64().unwrap() as f32,
-            cp_location_raw[2].as_f64().unwrap() as f32,
+            number(&cp_location_raw[0]).unwrap() as f32,
+            number(&cp_location_raw[1]).unwrap() as f32,
+            number(&cp_location_raw[2]).unwrap() as f32,
         );
 
         Self {
@@ -124,8 +147,20 @@ impl Settings {
             linkage_ratio,
             max_tvc,
             wind_speed,
+            atmosphere,
             cp_location,
             mmoi,
         }
     }
 }
+
+#[test]
+fn test_load_settings() {
+    let settings = Settings::load("config/settings.yaml".into());
+    // written as integers
+    assert_eq!(settings.imu_gyro_read_speed, 500.0);
+    assert_eq!(settings.linkage_ratio, 4.0);
+    assert_eq!(settings.tvc_location, Vec3::new(0.4, 0.0, 0.0));
+    assert_eq!(settings.cp_location, Vec3::new(-0.4, 0.0, 0.0));
+    assert_eq!(settings.motors.len(), 2);
+}
@@ -1 +1,400 @@
+//! the software on the rocket's flight computer, which only gets to see the sensors
 
+use std::time::Duration;
+
+use rocketengine::motor::Motor;
+
+use crate::control::{AttitudeEstimator, AttitudeFilter, FSF, PID};
+use crate::data::{ControllData, FlightPath, Settings};
+use crate::math::{calculate_angle_fom_desired_torque, clamp, Quaternion, Vec3, DEG_TO_RAD};
+use crate::sensors::{GpsFix, Reading, SampleClock};
+
+/// the latest reading from each sensor, None if there isn't one (yet, or at all)
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct SensorData {
+    /// when the flight computer is looking, secs
+    pub time: f32,
+    /// rad/s, body frame
+    pub gyro: Option<Reading<Vec3>>,
+    /// m/s^2, body frame
+    pub accel: Option<Reading<Vec3>>,
+    /// Pa
+    pub baro: Option<Reading<f32>>,
+    pub gps: Option<Reading<GpsFix>>,
+    /// rad/s relative to the rocket, from the reaction wheel's motor
+    pub reaction_wheel_speed: Option<f32>,
+}
+
+/// flight software, which is run every loop with what the sensors say and decides what to do about it
+pub trait FlightComputer {
+    fn step(&mut self, sensors: &SensorData) -> ControllData;
+}
+
+/// runs a flight computer at its own loop rate, holding its outputs in between
+pub struct FlightComputerRunner {
+    computer: Box<dyn FlightComputer + Send>,
+    clock: SampleClock,
+    output: ControllData,
+}
+
+impl FlightComputerRunner {
+    /// `rate` in hz
+    pub fn new(computer: impl FlightComputer + Send + 'static, rate: f32) -> Self {
+        Self {
+            computer: Box::new(computer),
+            clock: SampleClock::new(rate),
+            output: ControllData::default(),
+        }
+    }
+
+    /// what the flight computer last decided
+    pub fn output(&self) -> &ControllData {
+        &self.output
+    }
+
+    /// steps the flight computer if a loop is due, returning what it decided if it was
+    pub fn update(&mut self, sensors: &SensorData) -> Option<&ControllData> {
+        if !self.clock.due(sensors.time) {
+            return None;
+        }
+        self.output = self.computer.step(sensors);
+        Some(&self.output)
+    }
+}
+
+/// the controller turning the attitude error into an angular acceleration, one for each tvc axis
+#[derive(Debug, Clone, PartialEq)]
+pub enum TvcController {
+    Pid { y: PID, z: PID },
+    Fsf { y: FSF, z: FSF },
+}
+
+impl TvcController {
+    pub fn pid(kp: f32, ki: f32, kd: f32) -> Self {
+        // the setpoint is always 0, the error is worked out beforehand
+        let pid = PID::new(kp, ki, kd, 0.0, 1.0, false);
+        Self::Pid {
+            y: pid.clone(),
+            z: pid,
+        }
+    }
+
+    pub fn fsf(gain_a: f32, gain_b: f32) -> Self {
+        Self::Fsf {
+            y: FSF::new(gain_a, gain_b),
+            z: FSF::new(gain_a, gain_b),
+        }
+    }
+
+    /// the angular acceleration (rad/s^2) wanted about y and z, from how far the rocket is turned from the setpoint
+    /// about each (`error`, rad, body frame) and how fast it is turning (`rate`, rad/s)
+    fn compute(&mut self, error: Vec3, rate: Vec3, dt: f32) -> (f32, f32) {
+        match self {
+            Self::Pid { y, z } => {
+                let dt = Duration::from_secs_f32(dt);
+                y.compute(-error.y, dt);
+                z.compute(-error.z, dt);
+                (y.output(), z.output())
+            }
+            Self::Fsf { y, z } => {
+                y.compute(-error.y, rate.y);
+                z.compute(-error.z, rate.z);
+                (y.get_output(), z.get_output())
+            }
+        }
+    }
+}
+
+/**
+ * ZegeSim's flight software: keeps the rocket pointing along the flight path by moving the tvc mount
+ *
+ * it estimates the attitude from the imu (calibrating on the pad), and runs a pid or fsf on each axis to get the
+ * angular acceleration it wants, which is turned into a tvc angle from the thrust (what the accelerometer feels, times
+ * the mass left), the mmoi and how far the mount is from the center of mass. the mount is held straight until it feels
+ * the rocket lift off, and again once there is too little thrust to steer with (coasting after burnout)
+ */
+#[derive(Debug, Clone)]
+pub struct TvcFlightComputer {
+    controller: TvcController,
+    attitude: AttitudeEstimator,
+    flight_path: Option<FlightPath>,
+    /// at liftoff, kg
+    mass: f32,
+    /// kg of propellant burnt, and how long it takes (secs) from liftoff
+    propellant: (f32, f32),
+    mmoi: Vec3,
+    /// how far the tvc mount is from the center of mass along the rocket, m
+    moment_arm: f32,
+    /// rad
+    max_tvc: Vec3,
+    /// it only steers with more thrust than this, n
+    min_thrust: f32,
+    /// the motor to fire and when, and if it has been
+    ignition: Option<(String, f32)>,
+    fired: bool,
+    /// when the accelerometer felt the motor lift the rocket
+    liftoff: Option<f32>,
+    last_time: Option<f32>,
+}
+
+/// m/s^2 along the rocket it takes to have lifted off, well over the 1g it feels sitting on the pad
+const LIFTOFF_ACCEL: f32 = 1.5 * 9.80665;
+
+impl TvcFlightComputer {
+    pub fn new(controller: TvcController, mass: f32, mmoi: Vec3, moment_arm: f32) -> Self {
+        Self {
+            controller,
+            attitude: AttitudeEstimator::new(AttitudeFilter::mekf()),
+            flight_path: None,
+            mass,
+            propellant: (0.0, 0.0),
+            mmoi,
+            moment_arm,
+            max_tvc: Vec3::new(0.0, 15.0, 15.0) * DEG_TO_RAD,
+            min_thrust: 1.0,
+            ignition: None,
+            fired: false,
+            liftoff: None,
+            last_time: None,
+        }
+    }
+
+    /// the rocket from the settings, with a pid, lifted off by the first motor
+    pub fn from_settings(settings: &Settings) -> Self {
+        // grams
+        let motors: f64 = settings.motors.iter().map(|(_, m)| m.total_weight).sum();
+        let mass = settings.mass + motors as f32 / 1000.0;
+        let computer = Self::new(
+            TvcController::pid(20.0, 0.0, 5.0),
+            mass,
+            settings.mmoi,
+            settings.tvc_location.x,
+        )
+        .max_tvc(settings.max_tvc * DEG_TO_RAD);
+        match settings.motors.first() {
+            Some((_, motor)) => computer.propellant(
+                motor.propellant_weight as f32 / 1000.0,
+                Motor::from(motor).burn_time() as f32,
+            ),
+            None => computer,
+        }
+    }
+
+    /// burns `mass` kg of propellant evenly over `burn_time` secs from liftoff, so the thrust is worked out from the mass
+    /// the rocket has left. without this the liftoff mass is used for the whole flight
+    pub fn propellant(mut self, mass: f32, burn_time: f32) -> Self {
+        self.propellant = (mass, burn_time);
+        self
+    }
+
+    pub fn attitude_estimator(mut self, estimator: AttitudeEstimator) -> Self {
+        self.attitude = estimator;
+        self
+    }
+
+    /// setpoints to follow (degrees), without one the rocket is kept pointing straight up
+    pub fn flight_path(mut self, flight_path: FlightPath) -> Self {
+        self.flight_path = Some(flight_path);
+        self
+    }
+
+    /// the most the tvc mount can move about y and z, rad
+    pub fn max_tvc(mut self, max_tvc: Vec3) -> Self {
+        self.max_tvc = max_tvc;
+        self
+    }
+
+    /// the least thrust it steers with, n
+    pub fn min_thrust(mut self, min_thrust: f32) -> Self {
+        self.min_thrust = min_thrust;
+        self
+    }
+
+    /// fires `motor` at `time`, until then it sits calibrating. without this it never calibrates
+    pub fn fire_at(mut self, motor: String, time: f32) -> Self {
+        self.ignition = Some((motor, time));
+        self
+    }
+
+    /// which way the flight computer thinks it is pointing
+    pub fn attitude(&self) -> Quaternion {
+        self.attitude.estimate()
+    }
+
+    /// what the rocket weighs at `time`, kg
+    fn mass_at(&self, time: f32) -> f32 {
+        let (propellant, burn_time) = self.propellant;
+        let burnt = match self.liftoff {
+            Some(liftoff) if burn_time > 0.0 => clamp((time - liftoff) / burn_time, 0.0, 1.0),
+            Some(_) => 1.0,
+            None => 0.0,
+        };
+        self.mass - propellant * burnt
+    }
+
+    fn setpoint(&mut self, time: f32) -> Quaternion {
+        match &mut self.flight_path {
+            Some(path) => Quaternion::from_euler(path.get_next_setpoint(time) * DEG_TO_RAD),
+            None => Quaternion::default(),
+        }
+    }
+}
+
+impl FlightComputer for TvcFlightComputer {
+    fn step(&mut self, sensors: &SensorData) -> ControllData {
+        let time = sensors.time;
+        let dt = self.last_time.map_or(0.0, |last| time - last);
+        self.last_time = Some(time);
+        let mut output = ControllData::default();
+
+        let (Some(gyro), Some(accel)) = (sensors.gyro, sensors.accel) else {
+            return output;
+        };
+        if self.liftoff.is_none() {
+            match &self.ignition {
+                Some((motor, ignition)) if !self.fired && time >= *ignition => {
+                    output.motor_fire = motor.clone();
+                    self.fired = true;
+                }
+                // sits calibrating until it fires, or without a fire_at until something else lifts it off the pad
+                _ if !self.fired && accel.value.x <= LIFTOFF_ACCEL => {
+                    self.attitude.calibrate(gyro.value, accel.value);
+                    return output;
+                }
+                _ => {}
+            }
+            // on the pad the accelerometer feels 1g, which would look like plenty of thrust to steer with
+            if accel.value.x <= LIFTOFF_ACCEL {
+                return output;
+            }
+            self.liftoff = Some(time);
+        }
+        if dt <= 0.0 {
+            return output;
+        }
+        self.attitude.update(gyro.value, accel.value, dt);
+
+        // coasting, the accelerometer only feels drag, which would flip the mount from one side to the other
+        let thrust = accel.value.x * self.mass_at(time);
+        if thrust <= self.min_thrust {
+            return output;
+        }
+
+        // how far the rocket needs to turn to get to the setpoint, in its own frame
+        let (axis, angle) = (self.attitude().conj() * self.setpoint(time)).to_axis_angle();
+        let rate = gyro.value - self.attitude.gyro_bias();
+        let (wanted_y, wanted_z) = self.controller.compute(axis * angle, rate, dt);
+
+        let tvc_angle = |wanted: f32, mmoi: f32, max: f32| {
+            clamp(
+                calculate_angle_fom_desired_torque(self.moment_arm, thrust, mmoi, wanted),
+                -max,
+                max,
+            )
+        };
+        output.tvc_position = Vec3::new(
+            0.0,
+            tvc_angle(wanted_y, self.mmoi.y, self.max_tvc.y),
+            tvc_angle(wanted_z, self.mmoi.z, self.max_tvc.z),
+        );
+        output
+    }
+}
+
+#[cfg(test)]
+fn still_sensors(time: f32, rotation: Quaternion, accel: f32) -> SensorData {
+    let reading = |value| Some(Reading { time, value });
+    SensorData {
+        time,
+        gyro: reading(Vec3::default()),
+        accel: reading(rotation.conj().rotate(Vec3::new(accel, 0.0, 0.0))),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_tvc_flight_computer() {
+    for controller in [
+        TvcController::pid(20.0, 0.0, 5.0),
+        TvcController::fsf(20.0, 5.0),
+    ] {
+        let mut computer =
+            TvcFlightComputer::new(controller, 1.0, Vec3::new(0.05, 0.05, 0.05), 0.4)
+                .min_thrust(0.05)
+                .fire_at("main".into(), 1.0);
+        let tilt = Quaternion::from_axis_angle(Vec3::new(0.0, 1.0, 0.0), 5f32.to_radians());
+
+        // sits calibrating until it is time to fire
+        for step in 0..100 {
+            let out = computer.step(&still_sensors(step as f32 / 100.0, tilt, 9.80665));
+            assert_eq!(out, ControllData::default());
+        }
+        assert_eq!(
+            computer.step(&still_sensors(1.0, tilt, 9.80665)).motor_fire,
+            "main"
+        );
+
+        // boosting while tipped over about y, it pushes back about y
+        let out = computer.step(&still_sensors(1.01, tilt, 20.0));
+        assert!(out.motor_fire.is_empty());
+        assert!(out.tvc_position.y < 0.0);
+        assert!(out.tvc_position.z.abs() < 1e-3);
+        assert!(out.tvc_position.y >= -15f32.to_radians());
+
+        // far too little thrust to turn as fast as it wants, so it gives it all the mount has
+        let out = computer.step(&still_sensors(1.02, tilt, 0.1));
+        assert_eq!(out.tvc_position.y, -15f32.to_radians());
+
+        // coasting, drag is all the accelerometer feels and it holds the mount straight
+        let out = computer.step(&still_sensors(1.03, tilt, -2.0));
+        assert_eq!(out.tvc_position, Vec3::default());
+    }
+}
+
+#[test]
+fn test_tvc_liftoff() {
+    let mut computer = TvcFlightComputer::new(TvcController::fsf(20.0, 5.0), 1.0, Vec3::new(0.05, 0.05, 0.05), 0.4)
+        .propellant(0.2, 2.0);
+    let tilt = Quaternion::from_axis_angle(Vec3::new(0.0, 1.0, 0.0), 5f32.to_radians());
+
+    // lit by something else, the 1g on the pad is not thrust to steer with
+    for step in 0..100 {
+        let out = computer.step(&still_sensors(step as f32 / 100.0, tilt, 9.80665));
+        assert_eq!(out, ControllData::default());
+    }
+    assert_eq!(computer.mass_at(1.0), 1.0);
+
+    let out = computer.step(&still_sensors(1.0, tilt, 20.0));
+    assert!(out.tvc_position.y < 0.0);
+    // burning off the propellant from liftoff
+    assert!((computer.mass_at(2.0) - 0.9).abs() < 1e-6);
+    assert!((computer.mass_at(10.0) - 0.8).abs() < 1e-6);
+}
+
+#[test]
+fn test_runner() {
+    struct Counter(u32);
+    impl FlightComputer for Counter {
+        fn step(&mut self, _: &SensorData) -> ControllData {
+            self.0 += 1;
+            ControllData {
+                reaction_wheel_output: self.0 as f32,
+                ..Default::default()
+            }
+        }
+    }
+
+    let mut runner = FlightComputerRunner::new(Counter(0), 100.0);
+    let ran = (0..1000)
+        .filter(|step| {
+            runner
+                .update(&SensorData {
+                    time: *step as f32 / 1000.0,
+                    ..Default::default()
+                })
+                .is_some()
+        })
+        .count();
+    assert_eq!(ran, 100);
+    assert_eq!(runner.output().reaction_wheel_output, 100.0);
+}
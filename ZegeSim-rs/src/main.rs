@@ -5,12 +5,172 @@ pub mod math;
 pub mod motor;
 pub mod physics;
 pub mod rocket_body;
+pub mod sensors;
 
 /*
 notes:
 
-sensors.py is unnecessary, as it basicaly adds artificial noise to sensors (mabey later)
-if this is done, then a kalman must be implemented to filter this noise (also later)
+sensors add artificial noise (and the other errors real ones have) to what the flight code sees
+the kalman (control/kalman.rs) filters this noise back out for the altitude
 */
 
-fn main() {}
+use std::fs::{create_dir_all, File};
+use std::io::Write;
+use std::path::PathBuf;
+
+use data::{FlightPath, Settings};
+use flight_code::TvcFlightComputer;
+use math::RAD_TO_DEG;
+use rocket_body::RocketBody;
+
+/// when the flight computer fires the first motor, giving the sensors a second on the pad to calibrate
+const IGNITION_TIME: f32 = 1.0;
+
+const LOG_HEADER: [&str; 37] = [
+    "time",
+    "position_x",
+    "position_y",
+    "position_z",
+    "velocity_x",
+    "velocity_y",
+    "velocity_z",
+    "acceleration_x",
+    "acceleration_y",
+    "acceleration_z",
+    "rotation_x",
+    "rotation_y",
+    "rotation_z",
+    "rotational_velocity_x",
+    "rotational_velocity_y",
+    "rotational_velocity_z",
+    "setpoint_x",
+    "setpoint_y",
+    "setpoint_z",
+    "tvc_command_y",
+    "tvc_command_z",
+    "tvc_y",
+    "tvc_z",
+    "thrust",
+    "mass",
+    "altitude_estimate",
+    "altitude_error",
+    "baro_time",
+    "baro_pressure",
+    "baro_altitude",
+    "gps_time",
+    "gps_position_x",
+    "gps_position_y",
+    "gps_position_z",
+    "gps_velocity_x",
+    "gps_velocity_y",
+    "gps_velocity_z",
+];
+
+/// everything about the rocket right now, angles in degrees, in the order of LOG_HEADER. sensors that have not read
+/// anything yet are logged as 0
+fn log_row(rocket: &RocketBody, setpoint: math::Vec3) -> [f32; 37] {
+    let body = rocket.body();
+    let rotation = body.rotation.to_euler() * RAD_TO_DEG;
+    let rotational_velocity = body.rotational_velocity_local * RAD_TO_DEG;
+    let command = rocket
+        .flight_computer()
+        .map_or(math::Vec3::default(), |fc| fc.output().tvc_position)
+        * RAD_TO_DEG;
+    let tvc = rocket.tvc().position() * RAD_TO_DEG;
+    let estimate = rocket.estimation_errors().last();
+    let baro = rocket.barometer();
+    let baro_reading = baro.and_then(|baro| baro.reading());
+    let gps = rocket.gps().and_then(|gps| gps.reading());
+    let gps_fix = gps.map_or(Default::default(), |gps| (gps.value.position, gps.value.velocity));
+    [
+        rocket.get_time_seconds(),
+        body.position.x,
+        body.position.y,
+        body.position.z,
+        body.velocity.x,
+        body.velocity.y,
+        body.velocity.z,
+        body.acceleration.x,
+        body.acceleration.y,
+        body.acceleration.z,
+        rotation.x,
+        rotation.y,
+        rotation.z,
+        rotational_velocity.x,
+        rotational_velocity.y,
+        rotational_velocity.z,
+        setpoint.x,
+        setpoint.y,
+        setpoint.z,
+        command.y,
+        command.z,
+        tvc.y,
+        tvc.z,
+        rocket.rocket_motor().current_thrust(),
+        body.mass,
+        estimate.map_or(0.0, |e| e.position),
+        estimate.map_or(0.0, |e| e.position_error()),
+        baro_reading.map_or(0.0, |reading| reading.time),
+        baro_reading.map_or(0.0, |reading| reading.value),
+        baro.and_then(|baro| baro.altitude()).unwrap_or(0.0),
+        gps.map_or(0.0, |reading| reading.time),
+        gps_fix.0.x,
+        gps_fix.0.y,
+        gps_fix.0.z,
+        gps_fix.1.x,
+        gps_fix.1.y,
+        gps_fix.1.z,
+    ]
+}
+
+/// flies the rocket in config/settings.yaml along config/flight_path.csv, logging to out/zegesim.csv. the seed for
+/// the sensor noise and ignition delays can be given as the first argument
+fn main() {
+    let seed = std::env::args()
+        .nth(1)
+        .map_or(0, |seed| seed.parse().expect("the seed should be a number"));
+    let settings = Settings::load(PathBuf::from("config/settings.yaml"));
+    let mut flight_path = FlightPath::from_file(PathBuf::from("config/flight_path.csv"));
+
+    let mut rocket = RocketBody::from_settings(&settings);
+    rocket.set_seed(seed);
+    rocket.set_kalman(control::Kalman::new());
+    let (first_motor, _) = settings.motors.first().expect("the rocket needs a motor");
+    let computer = TvcFlightComputer::from_settings(&settings)
+        .flight_path(flight_path.clone())
+        .fire_at(first_motor.clone(), IGNITION_TIME);
+    // the flight computer runs as fast as the gyro
+    rocket.set_flight_computer(computer, settings.imu_gyro_read_speed);
+
+    create_dir_all("out").unwrap();
+    let mut file = File::create("out/zegesim.csv").unwrap();
+    writeln!(file, "# seed: {}", seed).unwrap();
+    let mut log = csv::Writer::from_writer(file);
+    log.write_record(LOG_HEADER).unwrap();
+
+    let mut apogee: f32 = 0.0;
+    while rocket.get_time_seconds() < settings.simulation_time && !rocket.landed() {
+        rocket.update();
+        let setpoint = flight_path.get_next_setpoint(rocket.get_time_seconds());
+        log.write_record(log_row(&rocket, setpoint).map(|value| value.to_string()))
+            .unwrap();
+        rocket.clear();
+        apogee = apogee.max(rocket.body().position.x);
+
+        for event in rocket.poll_events() {
+            println!("{:>8.3}s {}", event.time, event.event);
+        }
+    }
+    log.flush().unwrap();
+
+    let body = rocket.body();
+    println!(
+        "apogee {:.1}m, flew for {:.2}s",
+        apogee,
+        rocket.get_time_seconds()
+    );
+    println!(
+        "ended {:.1}m up, {:.1}m along y and {:.1}m along z from the pad",
+        body.position.x, body.position.y, body.position.z
+    );
+}
@@ -0,0 +1,105 @@
+//! just enough matrix math for the filters, on plain arrays
+
+use crate::math::Vec3;
+
+/// `R` rows of `C` columns
+pub type Matrix<const R: usize, const C: usize> = [[f32; C]; R];
+
+pub fn identity<const N: usize>() -> Matrix<N, N> {
+    let mut out = [[0.0; N]; N];
+    for (i, row) in out.iter_mut().enumerate() {
+        row[i] = 1.0;
+    }
+    out
+}
+
+pub fn multiply<const R: usize, const N: usize, const C: usize>(
+    a: Matrix<R, N>,
+    b: Matrix<N, C>,
+) -> Matrix<R, C> {
+    let mut out = [[0.0; C]; R];
+    for (out_row, a_row) in out.iter_mut().zip(a) {
+        for (j, value) in out_row.iter_mut().enumerate() {
+            *value = a_row.iter().zip(b).map(|(a, b_row)| a * b_row[j]).sum();
+        }
+    }
+    out
+}
+
+pub fn transpose<const R: usize, const C: usize>(a: Matrix<R, C>) -> Matrix<C, R> {
+    let mut out = [[0.0; R]; C];
+    for (i, row) in a.iter().enumerate() {
+        for (j, value) in row.iter().enumerate() {
+            out[j][i] = *value;
+        }
+    }
+    out
+}
+
+pub fn add<const R: usize, const C: usize>(mut a: Matrix<R, C>, b: Matrix<R, C>) -> Matrix<R, C> {
+    for (a_row, b_row) in a.iter_mut().zip(b) {
+        for (a, b) in a_row.iter_mut().zip(b_row) {
+            *a += b;
+        }
+    }
+    a
+}
+
+pub fn subtract<const R: usize, const C: usize>(a: Matrix<R, C>, b: Matrix<R, C>) -> Matrix<R, C> {
+    add(a, b.map(|row| row.map(|value| -value)))
+}
+
+/// the inverse of a 3x3 matrix, None if it can't be inverted
+pub fn inverse3(m: Matrix<3, 3>) -> Option<Matrix<3, 3>> {
+    let cofactor =
+        |r0: usize, r1: usize, c0: usize, c1: usize| m[r0][c0] * m[r1][c1] - m[r0][c1] * m[r1][c0];
+    let adjugate = [
+        [
+            cofactor(1, 2, 1, 2),
+            -cofactor(0, 2, 1, 2),
+            cofactor(0, 1, 1, 2),
+        ],
+        [
+            -cofactor(1, 2, 0, 2),
+            cofactor(0, 2, 0, 2),
+            -cofactor(0, 1, 0, 2),
+        ],
+        [
+            cofactor(1, 2, 0, 1),
+            -cofactor(0, 2, 0, 1),
+            cofactor(0, 1, 0, 1),
+        ],
+    ];
+    let determinant =
+        m[0][0] * adjugate[0][0] + m[0][1] * adjugate[1][0] + m[0][2] * adjugate[2][0];
+    if determinant.abs() < f32::EPSILON {
+        return None;
+    }
+    Some(adjugate.map(|row| row.map(|value| value / determinant)))
+}
+
+/// the matrix that does `v.cross(other)` when multiplied by `other`
+pub fn skew(v: Vec3) -> Matrix<3, 3> {
+    [[0.0, -v.z, v.y], [v.z, 0.0, -v.x], [-v.y, v.x, 0.0]]
+}
+
+#[test]
+fn test_matrix() {
+    let m = [[2.0, 0.0, 1.0], [1.0, 3.0, 0.0], [0.0, 1.0, 4.0]];
+    let inverse = inverse3(m).unwrap();
+    let product = multiply(m, inverse);
+    for (i, row) in product.iter().enumerate() {
+        for (j, value) in row.iter().enumerate() {
+            assert!((value - identity::<3>()[i][j]).abs() < 1e-6);
+        }
+    }
+    assert!(inverse3([[1.0, 2.0, 3.0], [2.0, 4.0, 6.0], [0.0, 1.0, 0.0]]).is_none());
+
+    let (a, b) = (Vec3::new(1.0, 2.0, 3.0), Vec3::new(-2.0, 0.5, 1.0));
+    let crossed = multiply(skew(a), [[b.x], [b.y], [b.z]]);
+    assert_eq!(
+        Vec3::new(crossed[0][0], crossed[1][0], crossed[2][0]),
+        a.cross(b)
+    );
+    assert_eq!(transpose([[1.0, 2.0]]), [[1.0], [2.0]]);
+}
@@ -1,7 +1,7 @@
 #[allow(unused_imports)]
 use assert_approx_eq::assert_approx_eq;
 use std::fmt::{Display, Formatter};
-use std::ops::{Mul, MulAssign};
+use std::ops::{Add, Mul, MulAssign};
 
 #[allow(unused_imports)]
 use crate::math::utils::{DEG_TO_RAD, RAD_TO_DEG};
@@ -63,6 +63,22 @@ impl MulAssign for Quaternion {
     }
 }
 
+impl Mul<f32> for Quaternion {
+    type Output = Self;
+
+    fn mul(self, other: f32) -> Self {
+        Quaternion::new(self.w * other, self.x * other, self.y * other, self.z * other)
+    }
+}
+
+impl Add for Quaternion {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Quaternion::new(self.w + other.w, self.x + other.x, self.y + other.y, self.z + other.z)
+    }
+}
+
 impl From<Vec3> for Quaternion {
     fn from(v: Vec3) -> Quaternion {
         Quaternion::new(0.0, v.x, v.y, v.z)
@@ -129,7 +145,7 @@ impl Quaternion {
 
     /// Return the quaternion from an axis and angle.
     pub fn from_axis_angle(axis: Vec3, angle: f32) -> Quaternion {
-        let sa = (angle / 2.0).cos();
+        let sa = (angle / 2.0).sin();
 
         let w = (angle / 2.0).cos();
         let x = axis.x * sa;
@@ -139,6 +155,38 @@ impl Quaternion {
         Quaternion::new(w, x, y, z)
     }
 
+    /// Return the axis and angle (radians) of the rotation, the opposite of from_axis_angle.
+    pub fn to_axis_angle(self) -> (Vec3, f32) {
+        // the shorter way round
+        let q = if self.w < 0.0 { self * -1.0 } else { self };
+        let v = Vec3::new(q.x, q.y, q.z);
+        if v.norm() < 1e-9 {
+            return (Vec3::new(1.0, 0.0, 0.0), 0.0);
+        }
+        (v.normalize(), 2.0 * v.norm().atan2(q.w))
+    }
+
+    /// Return the shortest rotation taking the direction of `from` to the direction of `to`.
+    pub fn between(from: Vec3, to: Vec3) -> Quaternion {
+        let (from, to) = (from.normalize(), to.normalize());
+        let axis = from.cross(to);
+        if axis.norm() < 1e-6 {
+            if from.dot(to) > 0.0 {
+                return Quaternion::default();
+            }
+            // opposite, so any axis at right angles will do
+            let other = if from.x.abs() < 0.9 { Vec3::new(1.0, 0.0, 0.0) } else { Vec3::new(0.0, 1.0, 0.0) };
+            return Quaternion::from_axis_angle(from.cross(other).normalize(), std::f32::consts::PI);
+        }
+        Quaternion::from_axis_angle(axis.normalize(), axis.norm().atan2(from.dot(to)))
+    }
+
+    /// Return the angle (radians) of the rotation between two quaternions.
+    pub fn angle_to(self, other: Quaternion) -> f32 {
+        let q = self.conj() * other;
+        2.0 * Vec3::new(q.x, q.y, q.z).norm().atan2(q.w.abs())
+    }
+
     /// Convert euler angles to a quaternion.
     pub fn from_euler(euler_angles: Vec3) -> Quaternion {
         let cr = (euler_angles.x / 2.0).cos();
@@ -159,16 +207,20 @@ impl Quaternion {
 
     /// Convert a quaternion to euler angles.
     pub fn to_euler(self) -> Vec3 {
-        let x = 2.0
-            * (self.w * self.x + self.y * self.z)
-                .atan2(1.0 - 2.0 * (self.x.powi(2) + self.y.powi(2)));
+        let x = (2.0 * (self.w * self.x + self.y * self.z))
+            .atan2(1.0 - 2.0 * (self.x.powi(2) + self.y.powi(2)));
         let y = (2.0 * (self.w * self.y - self.z * self.x)).asin();
-        let z = 2.0
-            * (self.w * self.z + self.x * self.y)
-                .atan2(1.0 - 2.0 * (self.y.powi(2) + self.z.powi(2)));
+        let z = (2.0 * (self.w * self.z + self.x * self.y))
+            .atan2(1.0 - 2.0 * (self.y.powi(2) + self.z.powi(2)));
 
         Vec3 { x, y, z }
     }
+
+    /// Convert a rotation from rocketengine's frame, where z is up instead of x.
+    pub fn from_rocketengine(q: rocketengine::math::Quaternion) -> Self {
+        let v = Vec3::from_rocketengine(rocketengine::math::F64x3::new(q.x(), q.y(), q.z()));
+        Quaternion::new(q.w() as f32, v.x, v.y, v.z)
+    }
 }
 
 #[test]
@@ -211,6 +263,7 @@ fn test_rotate() {
     assert_approx_eq!(vt.z, -1.0, 0.0001);
 }
 #[test]
+#[allow(clippy::excessive_precision)]
 fn test_euler_to_quaternion() {
     let e = Vec3::new(45.0 * DEG_TO_RAD, 45.0 * DEG_TO_RAD, 45.0 * DEG_TO_RAD);
     let q = Quaternion::from_euler(e);
@@ -228,6 +281,7 @@ fn test_euler_to_quaternion() {
     assert_approx_eq!(q.z, qt.z, 0.0001);
 }
 #[test]
+#[allow(clippy::excessive_precision)]
 fn test_quaternion_to_euler() {
     let q = Quaternion::new(
         0.8446231020115715,
@@ -241,11 +295,52 @@ fn test_quaternion_to_euler() {
     println!("{:?}", q);
     println!("{}", e * RAD_TO_DEG);
 
-    // assert_approx_eq!(e.x, et.x, 0.0001);
-    // assert_approx_eq!(e.y, et.y, 0.0001);
-    // assert_approx_eq!(e.z, et.z, 0.0001);
-    // FIXME low precision floats go brrr
-    assert_approx_eq!(e.x, et.x, 0.15);
-    assert_approx_eq!(e.y, et.y, 0.15);
-    assert_approx_eq!(e.z, et.z, 0.15);
+    assert_approx_eq!(e.x, et.x, 0.0001);
+    assert_approx_eq!(e.y, et.y, 0.0001);
+    assert_approx_eq!(e.z, et.z, 0.0001);
+
+    // and back again
+    let e = Quaternion::from_euler(Vec3::new(0.1, -0.2, 0.3)).to_euler();
+    assert_approx_eq!(e.x, 0.1, 0.0001);
+    assert_approx_eq!(e.y, -0.2, 0.0001);
+    assert_approx_eq!(e.z, 0.3, 0.0001);
+}
+#[test]
+fn test_axis_angle() {
+    let q = Quaternion::from_axis_angle(Vec3::new(0.0, 0.0, 1.0), 90.0 * DEG_TO_RAD);
+    let v = q.rotate(Vec3::new(1.0, 0.0, 0.0));
+
+    assert_approx_eq!(v.x, 0.0, 0.0001);
+    assert_approx_eq!(v.y, 1.0, 0.0001);
+    assert_approx_eq!(v.z, 0.0, 0.0001);
+}
+#[test]
+fn test_between() {
+    let from = Vec3::new(1.0, 0.0, 0.0);
+    for to in [Vec3::new(0.0, 2.0, 0.0), Vec3::new(1.0, 1.0, 1.0), Vec3::new(-1.0, 0.0, 0.0), from] {
+        let q = Quaternion::between(from, to);
+        let rotated = q.rotate(from);
+        let to = to.normalize();
+        assert_approx_eq!(rotated.x, to.x, 0.0001);
+        assert_approx_eq!(rotated.y, to.y, 0.0001);
+        assert_approx_eq!(rotated.z, to.z, 0.0001);
+    }
+    let q = Quaternion::from_axis_angle(Vec3::new(0.0, 0.0, 1.0), 0.3);
+    assert_approx_eq!(Quaternion::default().angle_to(q), 0.3, 0.0001);
+    let (axis, angle) = q.to_axis_angle();
+    assert_approx_eq!(axis.z, 1.0, 0.0001);
+    assert_approx_eq!(angle, 0.3, 0.0001);
+    assert_approx_eq!(q.angle_to(q * Quaternion::from_axis_angle(Vec3::new(1.0, 0.0, 0.0), -0.2)), 0.2, 0.0001);
+}
+#[test]
+fn test_rocketengine_frame() {
+    use rocketengine::math::F64x3;
+
+    let q = rocketengine::math::Quaternion::from_axis_angle(F64x3::new(1.0, 0.0, 0.0), 0.3);
+    let v = Vec3::new(1.0, 2.0, 3.0);
+    let expected = Vec3::from_rocketengine(q.rotate(v.to_rocketengine()));
+    let rotated = Quaternion::from_rocketengine(q).rotate(v);
+    assert_approx_eq!(rotated.x, expected.x, 0.0001);
+    assert_approx_eq!(rotated.y, expected.y, 0.0001);
+    assert_approx_eq!(rotated.z, expected.z, 0.0001);
 }
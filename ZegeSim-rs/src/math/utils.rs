@@ -1,7 +1,7 @@
 use std::f32::consts::PI;
 
 use micromath::vector::F32x2;
-use rand::{thread_rng, Rng};
+use rand::Rng;
 
 pub const DEG_TO_RAD: f32 = PI / 180.0;
 pub const RAD_TO_DEG: f32 = 180.0 / PI;
@@ -28,24 +28,31 @@ pub fn calculate_angle_fom_desired_torque(
 ) -> f32 {
     if force != 0.0 {
         let calcval = desired_torque * mmoi / force / moment_arm;
-        if calcval.abs() > 1.0 {
-            return 0.0;
-        } else {
-            return calcval.asin();
-        }
+        // more than the motor can give, so give it all it has
+        clamp(calcval, -1.0, 1.0).asin()
     } else {
-        return 0.0;
+        0.0
     }
 }
 
-pub fn positive_or_negative() -> i8 {
-    if thread_rng().gen_bool(0.5) {
+pub fn positive_or_negative(rng: &mut impl Rng) -> i8 {
+    if rng.gen_bool(0.5) {
         1
     } else {
         -1
     }
 }
 
+/// a normally distributed number (Box-Muller), for sensor noise
+pub fn gaussian(rng: &mut impl Rng, std_dev: f32) -> f32 {
+    if std_dev == 0.0 {
+        return 0.0;
+    }
+    let u: f32 = 1.0 - rng.gen::<f32>();
+    let v: f32 = rng.gen();
+    std_dev * (-2.0 * u.ln()).sqrt() * (2.0 * PI * v).cos()
+}
+
 pub fn lpf(new: f32, old: f32, gain: f32) -> f32 {
     new * (1.0 - gain) + old * gain
 }
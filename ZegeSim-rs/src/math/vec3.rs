@@ -5,6 +5,8 @@ use std::{
     },
 };
 
+use rocketengine::math::F64x3;
+
 /// A vector of three floats with a hek of a lot of operator overloading and utility functions
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Vec3 {
@@ -14,6 +16,7 @@ pub struct Vec3 {
 }
 
 #[test]
+#[allow(clippy::clone_on_copy)]
 fn test_copy_clone() {
     let original = Vec3::new(1.0, 1.0, 1.0);
     let copy = original;
@@ -69,6 +72,26 @@ impl Vec3 {
     pub fn angle_between(self, other: Vec3) -> f32 {
         (self.dot(other) / (self.norm() * other.norm())).acos()
     }
+
+    /// Convert to rocketengine's frame, where z is up instead of x.
+    pub fn to_rocketengine(self) -> F64x3 {
+        F64x3::new(self.y as f64, self.z as f64, self.x as f64)
+    }
+
+    /// Convert from rocketengine's frame, where z is up instead of x.
+    pub fn from_rocketengine(v: F64x3) -> Self {
+        Self::new(v.z as f32, v.x as f32, v.y as f32)
+    }
+}
+
+#[test]
+fn test_rocketengine_frame() {
+    let v = Vec3::new(1.0, 2.0, 3.0);
+    assert_eq!(v.to_rocketengine(), F64x3::new(2.0, 3.0, 1.0));
+    assert_eq!(Vec3::from_rocketengine(v.to_rocketengine()), v);
+    // still right handed
+    let (a, b) = (Vec3::new(0.0, 1.0, 0.0), Vec3::new(0.0, 0.0, 1.0));
+    assert_eq!(a.cross(b).to_rocketengine(), a.to_rocketengine().cross(b.to_rocketengine()));
 }
 
 #[test]
@@ -303,6 +326,7 @@ fn test_div_scalar() {
     assert_eq!(Vec3::new(1.0, 1.0, 1.0) / 2.0, Vec3::new(0.5, 0.5, 0.5));
 }
 #[test]
+#[allow(clippy::excessive_precision)]
 fn test_norm() {
     assert_eq!(
         Vec3::new(5.0, 5.0, 5.0).normalize(),
@@ -310,6 +334,7 @@ fn test_norm() {
     );
 }
 #[test]
+#[allow(clippy::excessive_precision)]
 fn test_len() {
     assert_eq!(Vec3::new(5.0, 5.0, 5.0).norm(), 8.660254037844387);
 }
@@ -410,6 +435,7 @@ fn test_index() {
 
 #[test]
 #[should_panic]
+#[allow(clippy::unnecessary_operation)]
 fn test_oob_index() {
     Vec3::default()[3usize];
 }
@@ -1,110 +1,219 @@
-pub mod spec;
-mod utils;
+use rand::Rng;
+use rocketengine::events::{self, Event};
+use rocketengine::motor::{Motor, MotorData};
 
-use rand::prelude::*;
-
-use spec::MotorSpec;
-
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone)]
 pub struct RocketMotor {
-    base_spec: MotorSpec,
-    thrust_list: Vec<f32>,
-    ignition_time: Option<f32>,
-    lit: bool,
-    // not changed, but the starting mass of the motor
-    mass: f32,
+    base_spec: MotorData,
+    /// thrust curve and propellant mass, shared with rocketengine
+    motor: Motor,
 }
 
 impl RocketMotor {
-    /// creates a new motor from the given spec, time step (for interpolation), and maximum ignition delay
-    pub fn new(spec: MotorSpec, time_step: f32, mass: f32) -> Self {
+    /// creates a new motor from the given spec (from the [`MotorCatalog`](rocketengine::motor::MotorCatalog))
+    pub fn new(spec: &MotorData) -> Self {
         Self {
-            base_spec: spec,
-            thrust_list: spec.into_interpolated_thrust(time_step),
-            ignition_time: None,
-            lit: false,
-            mass,
+            base_spec: spec.clone(),
+            motor: spec.into(),
         }
     }
 
-    pub fn mass(&self) -> f32 {
-        self.mass
+    /// multiplies the thrust (and so the total impulse) by `scale`
+    pub fn with_impulse_scale(mut self, scale: f32) -> Self {
+        self.motor = self.motor.with_impulse_scale(scale as f64);
+        self
     }
 
-    pub fn light(&mut self, time: f32, time_step: f32, max_delay: f32) {
-        if !self.lit {
-            self.lit = true;
-            self.ignition_time = Some(
-                time + (thread_rng().gen_range(80..=100) as f32 / 100.0 * max_delay) * time_step,
-            );
+    /// fires the ejection charge `delay` seconds after burnout, giving the rocket `impulse` newton seconds along its body
+    ///
+    /// the delay should be one of the delays in the spec, this does not check that it is
+    pub fn with_ejection(mut self, delay: f32, impulse: f32) -> Self {
+        self.motor = self.motor.with_ejection_delay(delay as f64).with_ejection_impulse(impulse as f64);
+        self
+    }
+
+    pub fn spec(&self) -> &MotorData {
+        &self.base_spec
+    }
+
+    /// mass of the motor (kg) at the given time, the propellant is burnt following the thrust curve
+    pub fn mass(&self, time: f32) -> f32 {
+        self.motor.weight_at(time as f64) as f32
+    }
+
+    /// thrust (newtons) at the given time
+    pub fn thrust(&self, time: f32) -> f32 {
+        self.motor
+            .time_since_ignition(time as f64)
+            .map_or(0.0, |t| self.motor.thrust_at(t) as f32)
+    }
+
+    /// lights the motor, it starts burning up to `max_delay` seconds later
+    pub fn light(&mut self, time: f32, max_delay: f32, rng: &mut impl Rng) {
+        if !self.lit() {
+            let delay = rng.gen_range(80..=100) as f32 / 100.0 * max_delay;
+            self.motor.light((time + delay) as f64);
         }
     }
 
     pub fn lit(&self) -> bool {
-        self.lit
+        self.motor.lit()
+    }
+
+    /// newton seconds the ejection charge gives the rocket, along its body
+    pub fn ejection_impulse(&self) -> f32 {
+        self.motor.ejection_impulse() as f32
+    }
+
+    /// when the motor started (or will start) burning, once it has been lit
+    pub fn ignition_time(&self) -> Option<f32> {
+        self.motor.ignition_time().map(|t| t as f32)
+    }
+
+    /// when the motor runs out of propellant, once it has been lit
+    pub fn burnout_time(&self) -> Option<f32> {
+        self.ignition_time().map(|t| t + self.motor.burn_time() as f32)
+    }
+
+    pub fn burning(&self, time: f32) -> bool {
+        self.ignition_time().is_some_and(|t| t <= time) && !self.burnt_out(time)
+    }
+
+    pub fn burnt_out(&self, time: f32) -> bool {
+        self.motor.burnt_out(time as f64)
     }
 
     /// #\[must_not_use]
+    #[allow(clippy::missing_safety_doc)]
     pub const unsafe fn explode(self) -> ! {
         std::hint::unreachable_unchecked();
     }
 }
 
-//TODO make this use a ordered sequence of motors (fire one after another) instead of a list of names and motors
-pub struct RocketEngineSystem {
+/// what lights a group of motors
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IgnitionTrigger {
+    /// when [`RocketEngineSystem::fire`] is called
+    Command,
+    /// once every motor in the group before it has burnt out
+    Burnout,
+    /// this many seconds after the group before it was lit
+    Delay(f32),
+}
+
+/// motors that are lit together
+#[derive(Debug, Clone)]
+struct MotorGroup {
+    trigger: IgnitionTrigger,
     motors: Vec<(String, RocketMotor)>,
-    time_step: f32,
+}
+
+impl MotorGroup {
+    fn lit(&self) -> bool {
+        self.motors.iter().all(|(_, m)| m.lit())
+    }
+
+    /// when the first motor in the group was lit
+    fn ignition_time(&self) -> Option<f32> {
+        self.motors.iter().filter_map(|(_, m)| m.ignition_time()).reduce(f32::min)
+    }
+
+    /// when the last motor in the group burns out, once they are all lit
+    fn burnout_time(&self) -> Option<f32> {
+        self.motors
+            .iter()
+            .map(|(_, m)| m.burnout_time())
+            .try_fold(0.0f32, |last, burnout| Some(last.max(burnout?)))
+    }
+
+    fn light(&mut self, time: f32, max_delay: f32, rng: &mut impl Rng) {
+        self.motors.iter_mut().for_each(|(_, m)| m.light(time, max_delay, rng));
+    }
+}
+
+/// the motors of a rocket, in groups that are fired one after another
+///
+/// every motor in a group is lit at the same time, and a group is lit by its [`IgnitionTrigger`] once the group
+/// before it has been lit. the first group has nothing before it, so `Burnout` and `Delay` count from the start
+/// of the flight
+pub struct RocketEngineSystem {
+    groups: Vec<MotorGroup>,
     max_ignition_delay: f32,
     total_mass: f32,
-    last_time: f32,
     // 1 = full
     throttle_precent: f32,
     current_thrust: f32,
 }
 
 impl RocketEngineSystem {
-    pub fn new(time_step: f32, max_ignition_delay: f32) -> Self {
+    pub fn new(max_ignition_delay: f32) -> Self {
         Self {
-            motors: vec![],
-            time_step,
+            groups: vec![],
             max_ignition_delay,
             total_mass: 0.0,
-            last_time: 0.0,
             throttle_precent: 1.0,
             current_thrust: 0.0,
         }
     }
 
-    /// Add a new motor after the others to be fired.
+    /// Add a new motor after the others to be fired, it is only lit on command. stage with [`add_group`](Self::add_group)
     pub fn add_motor(&mut self, name: String, motor: RocketMotor) {
-        self.total_mass += motor.mass();
-        self.motors.push((name, motor));
+        self.add_group(IgnitionTrigger::Command, vec![(name, motor)]);
+    }
+
+    /// Add a group of motors that are lit together, after the others to be fired.
+    pub fn add_group(&mut self, trigger: IgnitionTrigger, motors: Vec<(String, RocketMotor)>) {
+        self.total_mass += motors.iter().map(|(_, m)| m.mass(0.0)).sum::<f32>();
+        self.groups.push(MotorGroup { trigger, motors });
     }
 
-    pub fn ignite(&mut self, name: String, time: f32) {
-        for (n, m) in &mut self.motors {
-            if *n == name {
-                m.light(time, self.time_step, self.max_ignition_delay);
+    /// multiplies the thrust of every motor by `scale`
+    pub fn scale_impulse(&mut self, scale: f32) {
+        for group in &mut self.groups {
+            for (_, m) in &mut group.motors {
+                *m = m.clone().with_impulse_scale(scale);
             }
         }
     }
 
-    pub fn update(&mut self, time: f32) {
-        let dt = time - self.last_time;
-        for (_, m) in &mut self.motors {
-            if m.lit() {
-                //TODO make this operate inside the RocketMotor class instead of here
-                let counter = ((time * self.time_step) - m.ignition_time.unwrap()) as usize;
-                if 0 < counter && counter < m.thrust_list.len() {
-                    self.current_thrust = m.thrust_list[counter] * self.throttle_precent;
-                    self.total_mass -= 0.004 * dt;
-                } else {
-                    self.current_thrust = 0.0;
-                }
+    /// lights a single motor now, whatever group it is in
+    pub fn ignite(&mut self, name: String, time: f32, rng: &mut impl Rng) {
+        let max_delay = self.max_ignition_delay;
+        if let Some(m) = self.motor_mut(&name) {
+            m.light(time, max_delay, rng);
+        }
+    }
+
+    /// lights the next group that has not been lit yet, whatever its trigger
+    pub fn fire(&mut self, time: f32, rng: &mut impl Rng) {
+        if let Some(group) = self.groups.iter_mut().find(|g| !g.lit()) {
+            group.light(time, self.max_ignition_delay, rng);
+        }
+    }
+
+    /// `rng` picks the ignition delays of the motors that are lit
+    pub fn update(&mut self, time: f32, rng: &mut impl Rng) {
+        // lights any groups whose trigger has happened, at the time it happened
+        for i in 0..self.groups.len() {
+            if self.groups[i].lit() {
+                continue;
+            }
+            let before = i.checked_sub(1).map(|j| &self.groups[j]);
+            let ignition = match self.groups[i].trigger {
+                IgnitionTrigger::Command => None,
+                IgnitionTrigger::Burnout => before.map_or(Some(0.0), MotorGroup::burnout_time),
+                IgnitionTrigger::Delay(delay) => before.map_or(Some(0.0), MotorGroup::ignition_time).map(|t| t + delay),
+            };
+            match ignition {
+                Some(ignition) if ignition <= time => self.groups[i].light(ignition, self.max_ignition_delay, rng),
+                // the groups after this one wait for it
+                _ => break,
             }
         }
 
-        self.last_time = time;
+        // motors burning at the same time add up
+        self.current_thrust = self.motors().map(|(_, m)| m.thrust(time)).sum::<f32>() * self.throttle_precent;
+        self.total_mass = self.motors().map(|(_, m)| m.mass(time)).sum();
     }
 
     pub fn throttle(&mut self, percent: f32) {
@@ -115,7 +224,98 @@ impl RocketEngineSystem {
         self.total_mass
     }
 
+    /// mass (kg) of every motor at `time`, rather than at the last update
+    pub fn mass_at(&self, time: f32) -> f32 {
+        self.motors().map(|(_, m)| m.mass(time)).sum()
+    }
+
     pub fn current_thrust(&self) -> f32 {
         self.current_thrust
     }
+
+    /// every motor, in the order they are fired
+    pub fn motors(&self) -> impl Iterator<Item = (&String, &RocketMotor)> {
+        self.groups.iter().flat_map(|g| g.motors.iter().map(|(name, m)| (name, m)))
+    }
+
+    /// a motor by name, to check when it was lit or if it has burnt out
+    pub fn motor(&self, name: &str) -> Option<&RocketMotor> {
+        self.motors().find(|(n, _)| *n == name).map(|(_, m)| m)
+    }
+
+    fn motor_mut(&mut self, name: &str) -> Option<&mut RocketMotor> {
+        self.groups.iter_mut().flat_map(|g| g.motors.iter_mut()).find(|(n, _)| n == name).map(|(_, m)| m)
+    }
+
+    /// ignitions and burnouts in the step from `t0` to `t1`
+    pub fn events(&self, t0: f32, t1: f32) -> Vec<Event> {
+        self.motors()
+            .flat_map(|(name, m)| events::motor_events(name, &m.motor, t0 as f64, t1 as f64))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+use rand::SeedableRng;
+
+#[cfg(test)]
+fn test_motor(name: &str) -> RocketMotor {
+    RocketMotor::new(rocketengine::motor::MotorCatalog::builtin().find(name).unwrap())
+}
+
+#[test]
+fn test_sequence() {
+    let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+    let mut engines = RocketEngineSystem::new(0.0);
+    engines.add_motor("first".into(), test_motor("E12"));
+    engines.add_group(IgnitionTrigger::Burnout, vec![("second".into(), test_motor("E12"))]);
+    engines.add_group(IgnitionTrigger::Delay(0.5), vec![("third".into(), test_motor("F15"))]);
+    let burn_time = engines.motor("first").unwrap().motor.burn_time() as f32;
+
+    engines.update(1.0, &mut rng);
+    assert!(!engines.motor("first").unwrap().lit());
+    assert_eq!(engines.current_thrust(), 0.0);
+
+    engines.fire(1.0, &mut rng);
+    engines.update(1.0 + burn_time + 0.1, &mut rng);
+    assert!(engines.motor("first").unwrap().burnt_out(1.0 + burn_time + 0.1));
+    // lit right as the first burnt out, not when update was called
+    assert_eq!(engines.motor("second").unwrap().ignition_time(), Some(1.0 + burn_time));
+    assert!(engines.motor("second").unwrap().burning(1.0 + burn_time + 0.1));
+    assert!(!engines.motor("third").unwrap().lit());
+    engines.update(1.0 + burn_time + 1.0, &mut rng);
+    assert_eq!(engines.motor("third").unwrap().ignition_time(), Some(1.0 + burn_time + 0.5));
+}
+
+#[test]
+fn test_add_motor() {
+    let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+    let mut engines = RocketEngineSystem::new(0.0);
+    engines.add_motor("ascent".into(), test_motor("E12"));
+    engines.add_motor("descent".into(), test_motor("E12"));
+    engines.fire(0.0, &mut rng);
+    engines.update(10.0, &mut rng);
+    // nothing chains on its own, each waits to be fired
+    assert!(!engines.motor("descent").unwrap().lit());
+    engines.fire(10.0, &mut rng);
+    assert_eq!(engines.motor("descent").unwrap().ignition_time(), Some(10.0));
+}
+
+#[test]
+fn test_cluster() {
+    let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+    let mut engines = RocketEngineSystem::new(0.0);
+    engines.add_group(
+        IgnitionTrigger::Command,
+        vec![("a".into(), test_motor("E12")), ("b".into(), test_motor("E12"))],
+    );
+    let mut single = test_motor("E12");
+    single.light(0.0, 0.0, &mut rng);
+    engines.fire(0.0, &mut rng);
+    engines.update(1.0, &mut rng);
+    // both burning at once
+    assert_eq!(engines.current_thrust(), 2.0 * single.thrust(1.0));
+    assert_eq!(engines.total_mass(), 2.0 * single.mass(1.0));
+    // at any time, not only the last update
+    assert_eq!(engines.mass_at(0.5), 2.0 * single.mass(0.5));
 }
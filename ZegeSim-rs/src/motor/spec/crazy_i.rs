@@ -1,20 +0,0 @@
-pub const CRAZY_I_THRUST: [[f32; 2]; 18] = [
-    [0.021, 58.159],
-    [0.134, 95.015],
-    [0.289, 94.589],
-    [0.371, 89.476],
-    [0.485, 51.555],
-    [1.072, 41.116],
-    [2.155, 38.56],
-    [2.845, 38.347],
-    [3.619, 37.282],
-    [4.433, 36.429],
-    [5.474, 35.151],
-    [6.144, 34.725],
-    [6.794, 34.725],
-    [7.433, 34.299],
-    [7.969, 33.66],
-    [8.794, 32.382],
-    [9.546, 31.743],
-    [9.979, 0.0],
-];
@@ -1,33 +0,0 @@
-pub const E12_THRUST: [[f32; 2]; 31] = [
-    [0.052, 5.045],
-    [0.096, 9.910],
-    [0.196, 24.144],
-    [0.251, 31.351],
-    [0.287, 32.973],
-    [0.300, 29.910],
-    [0.344, 17.117],
-    [0.370, 14.414],
-    [0.400, 12.973],
-    [0.500, 11.712],
-    [0.600, 11.171],
-    [0.700, 10.631],
-    [0.800, 10.09],
-    [0.900, 9.73],
-    [1.000, 9.55],
-    [1.101, 9.91],
-    [1.200, 9.55],
-    [1.300, 9.73],
-    [1.400, 9.73],
-    [1.500, 9.73],
-    [1.600, 9.73],
-    [1.700, 9.55],
-    [1.800, 9.73],
-    [1.900, 9.73],
-    [2.000, 9.55],
-    [2.100, 9.55],
-    [2.200, 9.73],
-    [2.300, 9.19],
-    [2.375, 9.37],
-    [2.400, 5.95],
-    [2.440, 0.0],
-];
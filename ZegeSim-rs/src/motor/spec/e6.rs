@@ -1,21 +0,0 @@
-pub const E6_THRUST: [[f32; 2]; 19] = [
-    [0.056, 18.59],
-    [0.112, 20.12],
-    [0.168, 17.575],
-    [0.307, 14.38],
-    [0.531, 10.45],
-    [0.894, 7.696],
-    [1.146, 6.244],
-    [1.691, 5.808],
-    [2.836, 5.663],
-    [3.898, 5.517],
-    [4.275, 5.227],
-    [4.415, 4.937],
-    [5.058, 5.082],
-    [5.519, 5.227],
-    [5.603, 6.679],
-    [5.729, 3.921],
-    [5.882, 2.323],
-    [5.966, 1.016],
-    [6.06, 0.0],
-];
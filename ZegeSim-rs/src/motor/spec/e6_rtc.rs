@@ -1,26 +0,0 @@
-pub const E6_RTC_THRUST: [[f32; 2]; 24] = [
-    [0.0, 0.0],
-    [0.047, 10.866],
-    [0.127, 11.693],
-    [0.19, 11.9],
-    [0.316, 11.622],
-    [0.522, 10.593],
-    [0.743, 9.287],
-    [0.996, 7.842],
-    [1.249, 6.19],
-    [1.47, 5.296],
-    [1.787, 4.747],
-    [2.372, 4.471],
-    [3.02, 4.403],
-    [3.747, 4.264],
-    [4.49, 4.403],
-    [5.375, 4.333],
-    [6.087, 4.264],
-    [6.719, 4.264],
-    [6.877, 4.196],
-    [6.957, 3.783],
-    [7.004, 2.614],
-    [7.036, 1.513],
-    [7.083, 0.55],
-    [7.12, 0.0],
-];
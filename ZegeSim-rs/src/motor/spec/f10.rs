@@ -1,29 +0,0 @@
-pub const F10_THRUST: [[f32; 2]; 27] = [
-    [0.015, 28.22],
-    [0.077, 26.082],
-    [0.201, 24.934],
-    [0.31, 22.806],
-    [0.464, 20.183],
-    [0.573, 17.886],
-    [0.789, 16.075],
-    [1.068, 13.946],
-    [1.393, 12.63],
-    [1.718, 11.155],
-    [2.166, 9.844],
-    [2.677, 9.515],
-    [3.311, 9.187],
-    [3.683, 8.859],
-    [3.791, 9.679],
-    [4.101, 9.679],
-    [4.658, 9.515],
-    [5.168, 9.023],
-    [5.725, 9.023],
-    [6.112, 8.531],
-    [6.329, 8.859],
-    [6.499, 7.546],
-    [6.685, 5.742],
-    [6.778, 4.921],
-    [6.917, 2.625],
-    [7.025, 1.312],
-    [7.13, 0.0],
-];
@@ -1,24 +0,0 @@
-pub const G11_THRUST: [[f32; 2]; 22] = [
-    [0.084, 30.444],
-    [0.105, 28.414],
-    [0.209, 27.738],
-    [0.419, 24.085],
-    [0.753, 18.402],
-    [0.9, 14.748],
-    [1.046, 11.907],
-    [1.444, 10.013],
-    [2.051, 9.742],
-    [3.034, 9.201],
-    [4.018, 9.471],
-    [5.483, 9.201],
-    [5.713, 8.93],
-    [9.375, 9.742],
-    [9.501, 8.93],
-    [11.049, 9.742],
-    [12.263, 9.336],
-    [13.288, 8.66],
-    [13.456, 7.307],
-    [13.602, 4.6],
-    [13.77, 1.624],
-    [13.937, 0.0],
-];
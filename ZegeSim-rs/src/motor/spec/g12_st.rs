@@ -1,22 +0,0 @@
-pub const G12ST_THRUST: [[f32; 2]; 20] = [
-    [0.042, 33.827],
-    [0.104, 30.173],
-    [0.23, 28.009],
-    [0.543, 22.326],
-    [0.836, 16.102],
-    [1.024, 12.448],
-    [1.379, 10.96],
-    [2.006, 10.148],
-    [4.054, 9.742],
-    [6.269, 10.148],
-    [6.415, 10.148],
-    [6.582, 10.148],
-    [11.973, 9.742],
-    [12.475, 9.742],
-    [12.663, 9.607],
-    [12.83, 9.066],
-    [12.913, 7.713],
-    [12.934, 5.412],
-    [13.018, 2.03],
-    [13.06, 0.0],
-];
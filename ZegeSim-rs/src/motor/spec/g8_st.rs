@@ -1,27 +0,0 @@
-pub const G8ST_THRUST: [[f32; 2]; 25] = [
-    [0.038, 5.121],
-    [0.039, 8.069],
-    [0.188, 9.828],
-    [0.414, 10.397],
-    [0.715, 10.19],
-    [1.354, 9.517],
-    [2.069, 9.155],
-    [3.424, 8.793],
-    [4.552, 8.431],
-    [6.057, 8.276],
-    [6.81, 8.069],
-    [7.713, 8.121],
-    [9.03, 8.017],
-    [9.97, 7.966],
-    [10.76, 7.914],
-    [14.222, 7.397],
-    [14.335, 7.19],
-    [15.764, 7.138],
-    [16.404, 6.983],
-    [16.554, 7.5],
-    [16.63, 6.724],
-    [16.818, 5.69],
-    [16.968, 3.414],
-    [17.119, 1.655],
-    [17.269, 0.0],
-];
@@ -1,25 +0,0 @@
-pub const H13ST_THRUST: [[f32; 2]; 23] = [
-    [0.005, 0.107],
-    [0.024, 2.636],
-    [0.035, 18.978],
-    [0.081, 32.724],
-    [0.147, 36.421],
-    [0.379, 44.529],
-    [0.452, 23.851],
-    [0.566, 18.890],
-    [0.818, 16.728],
-    [1.286, 15.676],
-    [2.114, 14.753],
-    [3.230, 14.032],
-    [4.382, 13.926],
-    [5.786, 13.469],
-    [7.082, 13.119],
-    [8.666, 12.916],
-    [10.286, 12.820],
-    [12.086, 12.612],
-    [13.598, 12.333],
-    [14.750, 11.908],
-    [15.230, 11.078],
-    [15.302, 6.048],
-    [15.432, 0.0],
-];
@@ -1,5 +1,78 @@
+use std::sync::Arc;
+
+use rocketengine::atmosphere::Atmosphere;
+use rocketengine::gravity::{self, GravityModel};
+use rocketengine::rail::{LaunchRail, RailExit};
+use rocketengine::integrator::{Euler, IntegrationMethod, Integrator, State};
+use rocketengine::secs;
+
 use crate::math::{Quaternion, Vec3};
 
+/// the part of a [`PhysicsBody`] that is integrated over time
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BodyState {
+    pub position: Vec3,
+    pub velocity: Vec3,
+    pub rotation: Quaternion,
+    pub rotational_velocity: Vec3,
+}
+
+impl State for BodyState {
+    fn advance(&self, rate: &Self, dt: secs) -> Self {
+        let dt = dt as f32;
+        Self {
+            position: self.position + rate.position * dt,
+            velocity: self.velocity + rate.velocity * dt,
+            rotation: self.rotation + rate.rotation * dt,
+            rotational_velocity: self.rotational_velocity + rate.rotational_velocity * dt,
+        }
+    }
+
+    fn advance_velocity(&self, rate: &Self, dt: secs) -> Self {
+        let dt = dt as f32;
+        Self {
+            velocity: self.velocity + rate.velocity * dt,
+            rotational_velocity: self.rotational_velocity + rate.rotational_velocity * dt,
+            ..*self
+        }
+    }
+
+    fn drift(&self, dt: secs) -> Self {
+        let dt = dt as f32;
+        let ang = self.rotational_velocity.norm();
+        let rotation = if ang == 0.0 {
+            self.rotation
+        } else {
+            self.rotation * Quaternion::from_axis_angle(self.rotational_velocity / ang, ang * dt)
+        };
+        Self {
+            position: self.position + self.velocity * dt,
+            rotation,
+            ..*self
+        }
+    }
+
+    fn error(&self, other: &Self) -> f64 {
+        let diffs = [
+            (self.position - other.position).abs(),
+            (self.velocity - other.velocity).abs(),
+            (self.rotational_velocity - other.rotational_velocity).abs(),
+        ];
+        let rotation = (self.rotation + other.rotation * -1.0).norm();
+        diffs
+            .iter()
+            .flat_map(|d| [d.x, d.y, d.z])
+            .fold(rotation, f32::max) as f64
+    }
+
+    fn normalized(self) -> Self {
+        Self {
+            rotation: self.rotation.normalize(),
+            ..self
+        }
+    }
+}
+
 //TODO use builder pattern instead of this public field bs
 /**
 Class representing a rigid body in 3 dimensional space.
@@ -31,7 +104,8 @@ pub struct PhysicsBody {
     pub acceleration: Vec3,
     pub acceleration_local: Vec3,
 
-    pub gravity: Vec3,
+    /// positions are converted to rocketengine's frame for the model
+    pub gravity: Arc<dyn GravityModel>,
 
     pub rotation: Quaternion,
     pub rotation_euler: Vec3,
@@ -47,11 +121,24 @@ pub struct PhysicsBody {
     pub floor: bool,
 
     pub wind: Vec3,
+    /// the air around the body, x is the height above the launch site
+    pub atmosphere: Atmosphere,
     pub drag_force: Vec3,
     pub drag_area: f32,
     pub drag_coefficient: f32,
+    /// drag coefficient times area (m^2) of any deployed parachutes and streamers
+    pub recovery_drag_area: f32,
 
     pub aoa: f32,
+
+    /// seconds, advanced by update()
+    pub time: f32,
+
+    /// the launch rail, and where the body was when it was put on it
+    rail: Option<(LaunchRail, Vec3)>,
+    rail_exit: Option<RailExit>,
+
+    pub integrator: Box<dyn Integrator<BodyState>>,
 }
 
 impl Default for PhysicsBody {
@@ -62,7 +149,7 @@ impl Default for PhysicsBody {
 
             acceleration: Vec3::default(),
             acceleration_local: Vec3::default(),
-            gravity: Vec3::default(),
+            gravity: Arc::new(gravity::Constant::default()),
 
             rotation: Quaternion::default(),
             rotation_euler: Vec3::default(),
@@ -78,10 +165,19 @@ impl Default for PhysicsBody {
             floor: true,
 
             wind: Vec3::default(),
+            atmosphere: Atmosphere::standard(),
             drag_force: Vec3::default(),
             drag_area: 0.0,
             drag_coefficient: 0.0,
+            recovery_drag_area: 0.0,
             aoa: 0.0,
+
+            time: 0.0,
+
+            rail: None,
+            rail_exit: None,
+
+            integrator: IntegrationMethod::default().build(),
         }
     }
 }
@@ -98,7 +194,13 @@ impl PhysicsBody {
 
     /// Add a torque to the body.
     pub fn add_torque(&mut self, torque: Vec3) {
-        self.rotational_acceleration += torque / self.moment_of_inertia;
+        // an axis with no inertia set can not be turned, instead of spinning up infinitely fast
+        let per_axis = |torque: f32, inertia: f32| if inertia > 0.0 { torque / inertia } else { 0.0 };
+        self.rotational_acceleration += Vec3::new(
+            per_axis(torque.x, self.moment_of_inertia.x),
+            per_axis(torque.y, self.moment_of_inertia.y),
+            per_axis(torque.z, self.moment_of_inertia.z),
+        );
     }
 
     /// Add a force to the body in local space.
@@ -133,56 +235,153 @@ impl PhysicsBody {
         self.add_global_point_force(nf, point);
     }
 
-    /// Updates aerodynamic forces acting on the body.
-    /// Note - you still need to apply the drag force to the physics body with apply_glocal_point_force().
-    pub fn update_aero(&mut self) {
-        let velocity_relative_wind = self.velocity - self.wind;
+    /// drag force on the body, and its angle of attack, if it was at the given position, moving with the given velocity and rotation
+    fn aero(&self, position: Vec3, velocity: Vec3, rotation: Quaternion) -> Option<(Vec3, f32)> {
+        let velocity_relative_wind = velocity - self.wind;
 
         if velocity_relative_wind.x != 0.0
             && velocity_relative_wind.y != 0.0
             && velocity_relative_wind.z != 0.0
         {
-            self.aoa = velocity_relative_wind
-                .angle_between(self.rotation.rotate(Vec3::new(1.0, 0.0, 0.0)));
+            let aoa = velocity_relative_wind.angle_between(rotation.rotate(Vec3::new(1.0, 0.0, 0.0)));
 
-            let dc = self.drag_coefficient * self.aoa;
+            let dc = self.drag_coefficient * aoa;
 
-            if self.floor == false && self.position.x != 0.0 {
-                self.drag_force = -velocity_relative_wind.normalize()
-                    * 0.5
-                    * 1.225
-                    * (self.velocity.norm().powi(2))
-                    * dc
-                    * self.drag_area;
-            } else if self.floor == false {
-                self.drag_force = -velocity_relative_wind.normalize()
+            let density = self.atmosphere.at(position.x as f64).density as f32;
+
+            let drag_force = if !self.floor {
+                -velocity_relative_wind.normalize()
                     * 0.5
-                    * 1.225
-                    * (self.velocity.norm().powi(2))
+                    * density
+                    * (velocity.norm().powi(2))
                     * dc
-                    * self.drag_area;
-            }
+                    * self.drag_area
+            } else {
+                self.drag_force
+            };
+            Some((drag_force, aoa))
+        } else {
+            None
         }
     }
-    /// Updates the physics body
-    pub fn update(&mut self, dt: f32) {
-        self.acceleration_local = self.rotation.conj().rotate(self.acceleration);
 
-        self.acceleration += self.gravity;
+    /// Drag force from the recovery devices at a position and velocity.
+    fn recovery_drag(&self, position: Vec3, velocity: Vec3) -> Vec3 {
+        let air_velocity = velocity - self.wind;
+        let density = self.atmosphere.at(position.x as f64).density as f32;
+        -air_velocity * 0.5 * density * air_velocity.norm() * self.recovery_drag_area
+    }
 
-        self.velocity += self.acceleration * dt;
-        self.position += self.velocity * dt;
+    /// Acceleration due to gravity at a position.
+    pub fn gravity_at(&self, position: Vec3) -> Vec3 {
+        Vec3::from_rocketengine(self.gravity.acceleration(position.to_rocketengine()))
+    }
+
+    /// Put the body on a launch rail where it is now, pointing it up the rail.
+    /// It is held on the rail until it has travelled its length.
+    pub fn set_rail(&mut self, rail: LaunchRail) {
+        let direction = Vec3::from_rocketengine(rail.direction());
+        let nose = Vec3::new(1.0, 0.0, 0.0);
+        let axis = nose.cross(direction);
+        self.rotation = if axis.norm() > 0.0 {
+            Quaternion::from_axis_angle(axis.normalize(), nose.angle_between(direction))
+        } else {
+            Quaternion::default()
+        };
+        self.rotational_velocity = Vec3::default();
+        self.rail = Some((rail, self.position));
+        self.rail_exit = None;
+    }
 
-        self.rotational_velocity += self.rotational_acceleration * dt;
+    /// The launch rail the body was put on, if any.
+    pub fn rail(&self) -> Option<LaunchRail> {
+        self.rail.map(|(rail, _)| rail)
+    }
 
-        let mut ang = self.rotational_velocity.norm();
+    /// When and how fast (in rocketengine's frame) the body left the launch rail, once it has.
+    pub fn rail_exit(&self) -> Option<RailExit> {
+        self.rail_exit
+    }
 
-        if ang == 0.0 {
-            //lol
-            ang = 0.000000001;
+    /// The launch rail, and where it starts, if the body is on it at the given position.
+    fn on_rail(&self, position: Vec3) -> Option<(&LaunchRail, Vec3)> {
+        match &self.rail {
+            Some((rail, start))
+                if self.rail_exit.is_none()
+                    && rail.travelled(start.to_rocketengine(), position.to_rocketengine()) < rail.length =>
+            {
+                Some((rail, *start))
+            }
+            _ => None,
         }
+    }
 
-        self.rotation *= Quaternion::from_axis_angle(self.rotational_velocity / ang, ang * dt);
+    /// Updates aerodynamic forces acting on the body.
+    /// Note - the drag force is applied by update(), as it changes through the step. apply the torque from it with add_torque().
+    pub fn update_aero(&mut self) {
+        if let Some((drag_force, aoa)) = self.aero(self.position, self.velocity, self.rotation) {
+            self.drag_force = drag_force;
+            self.aoa = aoa;
+        }
+    }
+
+    /// Updates the physics body
+    pub fn update(&mut self, dt: f32) {
+        self.acceleration_local = self.rotation.conj().rotate(self.acceleration);
+
+        let state = BodyState {
+            position: self.position,
+            velocity: self.velocity,
+            rotation: self.rotation,
+            rotational_velocity: self.rotational_velocity,
+        };
+        // forces and torques added since the last clear() are held constant through the step, gravity and drag are not
+        let applied = self.acceleration;
+        let rotational_acceleration = self.rotational_acceleration;
+
+        let mut integrator = std::mem::replace(&mut self.integrator, Box::new(Euler));
+        let next = integrator.integrate(&state, 0.0, dt as secs, &mut |_, _, s: &BodyState| {
+            let drag = self.aero(s.position, s.velocity, s.rotation).map_or(self.drag_force, |(drag, _)| drag)
+                + self.recovery_drag(s.position, s.velocity);
+            let gravity = self.gravity_at(s.position);
+            if let Some((rail, _)) = self.on_rail(s.position) {
+                // held straight, and only moving along the rail
+                let acceleration = rail.constrain(
+                    (applied + gravity + drag / self.mass).to_rocketengine(),
+                    gravity.to_rocketengine(),
+                    s.velocity.to_rocketengine(),
+                );
+                return BodyState {
+                    position: s.velocity,
+                    velocity: Vec3::from_rocketengine(acceleration),
+                    rotation: Quaternion::new(0.0, 0.0, 0.0, 0.0),
+                    rotational_velocity: Vec3::default(),
+                };
+            }
+            BodyState {
+                position: s.velocity,
+                velocity: applied + gravity + drag / self.mass,
+                rotation: s.rotation * Quaternion::from(s.rotational_velocity) * 0.5,
+                rotational_velocity: rotational_acceleration,
+            }
+        });
+        self.integrator = integrator;
+
+        self.position = next.position;
+        self.velocity = next.velocity;
+        self.rotation = next.rotation;
+        self.rotational_velocity = next.rotational_velocity;
+
+        if let (Some((rail, start)), None) = (&self.rail, self.rail_exit) {
+            self.rail_exit = rail.exit(
+                start.to_rocketengine(),
+                self.time as secs,
+                dt as secs,
+                (state.position.to_rocketengine(), state.velocity.to_rocketengine()),
+                (next.position.to_rocketengine(), next.velocity.to_rocketengine()),
+            );
+        }
+        self.time += dt;
 
         self.rotation_euler = self.rotation.to_euler();
 
@@ -196,6 +395,8 @@ impl PhysicsBody {
             self.position.x = 0.0;
             self.velocity.x = 0.0;
         }
+        // what the body actually did, so sitting on the floor is no acceleration
+        self.acceleration = (self.velocity - state.velocity) / dt;
     }
 
     /// clears the rotational and translational acceleration
@@ -204,3 +405,37 @@ impl PhysicsBody {
         self.rotational_acceleration = Vec3::new(0.0, 0.0, 0.0);
     }
 }
+
+#[test]
+fn test_rail() {
+    let mut body = PhysicsBody::new();
+    body.set_rail(LaunchRail::new(1.0).elevation(60f64.to_radians()));
+    // the nose points up the rail, which leans north (+z here)
+    let nose = body.rotation.rotate(Vec3::new(1.0, 0.0, 0.0));
+    assert!((nose.x - 60f32.to_radians().sin()).abs() < 1e-5);
+    assert!((nose.z - 60f32.to_radians().cos()).abs() < 1e-5);
+
+    // nothing pushing it, so it stays on the pad
+    body.update(0.01);
+    assert_eq!(body.position, Vec3::default());
+
+    while body.rail_exit().is_none() {
+        body.clear();
+        body.add_force(Vec3::new(30.0, 0.0, 0.0));
+        body.update(0.01);
+        assert!(body.time < 2.0);
+    }
+    // pushed straight up, but only moved along the rail
+    let exit = body.rail_exit().unwrap();
+    let direction = LaunchRail::new(1.0).elevation(60f64.to_radians()).direction();
+    assert!((exit.velocity.normalize().dot(direction) - 1.0).abs() < 1e-4);
+}
+
+#[test]
+fn test_torque_without_inertia() {
+    let mut body = PhysicsBody::new();
+    body.moment_of_inertia = Vec3::new(0.0, 0.5, 0.5);
+    body.add_torque(Vec3::new(1.0, 1.0, -1.0));
+    // no roll inertia set, so no roll
+    assert_eq!(body.rotational_acceleration, Vec3::new(0.0, 2.0, -2.0));
+}
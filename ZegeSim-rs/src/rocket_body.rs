@@ -1,4 +1,19 @@
-use crate::{control::TVC, math::Vec3, motor::RocketEngineSystem, physics::PhysicsBody};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use rocketengine::dispersion::{Outcome, Sample};
+use rocketengine::events::{Ejection, Event, EventDetector, EventListener, FlightEvent, FlightSample};
+use rocketengine::rail::{LaunchRail, RailExit};
+use rocketengine::recovery::RecoveryDevice;
+
+use crate::{
+    control::{AttitudeEstimator, EstimationError, Kalman, ReactionWheel, TVC},
+    data::Settings,
+    flight_code::{FlightComputer, FlightComputerRunner, SensorData},
+    math::{Quaternion, Vec3},
+    motor::{IgnitionTrigger, RocketEngineSystem, RocketMotor},
+    physics::PhysicsBody,
+    sensors::{Barometer, Gps, Imu},
+};
 
 /// thing that go up
 pub struct RocketBody {
@@ -7,19 +22,46 @@ pub struct RocketBody {
     time: f32,
     time_step: f32,
 
-    //TODO add sensors herer
+    imu: Option<Imu>,
+    barometer: Option<Barometer>,
+    gps: Option<Gps>,
+    /// altitude estimate from the imu and barometer, and how far off it was each step
+    kalman: Option<Kalman>,
+    attitude: Option<AttitudeEstimator>,
+    flight_computer: Option<FlightComputerRunner>,
+    estimation_errors: Vec<EstimationError>,
     tvc: TVC,
     tvc_position: Vec3,
     tvc_location: Vec3,
+    /// torque (n*m) about the roll axis the reaction wheel was told to put on the rocket
     reaction_wheel_torque: f32,
+    reaction_wheel: Option<ReactionWheel>,
 
     //TODO make rocket engines (motors.py)
     rocket_motor: RocketEngineSystem,
+    /// where drag pushes on the rocket, from the center of mass
     cp_location: Vec3,
     dry_mass: f32,
+
+    /// parachutes and streamers
+    recovery: Vec<RecoveryDevice>,
+    /// ejection charges that have fired
+    ejections: Vec<Ejection>,
+
+    detector: EventDetector,
+    /// every event so far, in order
+    events: Vec<Event>,
+    /// how many events have been taken by poll_events
+    polled_events: usize,
+    listeners: Vec<EventListener>,
+
+    /// every random thing in the simulation draws from this, so a run can be repeated from its seed
+    seed: u64,
+    rng: StdRng,
 }
 
 impl RocketBody {
+    #[allow(clippy::new_without_default)]
     pub fn new() -> Self {
         Self {
             body: PhysicsBody::new(),
@@ -27,17 +69,106 @@ impl RocketBody {
             time: 0.0,
             time_step: 0.0,
 
+            imu: None,
+            barometer: None,
+            gps: None,
+            kalman: None,
+            attitude: None,
+            flight_computer: None,
+            estimation_errors: vec![],
             tvc: TVC::new(),
             tvc_position: Vec3::default(),
             tvc_location: Vec3::default(),
             reaction_wheel_torque: 0.0,
+            reaction_wheel: None,
 
-            rocket_motor: RocketEngineSystem::new(1000.0, 0.0), //TODO what is these values mean
-            cp_location: Vec3::default(),
+            rocket_motor: RocketEngineSystem::new(0.0),
+            cp_location: Vec3::new(-0.15, 0.0, 0.0),
             dry_mass: 1.0,
+
+            recovery: vec![],
+            ejections: vec![],
+
+            detector: EventDetector::new(),
+            events: vec![],
+            polled_events: 0,
+            listeners: vec![],
+
+            seed: 0,
+            rng: StdRng::seed_from_u64(0),
         }
     }
 
+    /// the rocket described by the settings, sitting on the pad with all its sensors. every motor waits to be fired
+    /// (by name, like a flight computer does)
+    pub fn from_settings(settings: &Settings) -> Self {
+        let mut rocket = Self::new();
+        // the settings have it as a rate
+        rocket.time_step = 1.0 / settings.time_step;
+        rocket.dry_mass = settings.mass;
+        rocket.body.moment_of_inertia = settings.mmoi;
+        rocket.body.drag_area = settings.drag_area;
+        rocket.body.drag_coefficient = settings.drag_coeff;
+        rocket.body.wind = settings.wind_speed;
+        rocket.body.atmosphere = settings.atmosphere;
+
+        rocket.tvc = TVC::from_settings(settings);
+        rocket.tvc_location = settings.tvc_location;
+        rocket.cp_location = settings.cp_location;
+
+        rocket.rocket_motor = RocketEngineSystem::new(settings.max_ignition_delay);
+        for (name, motor) in &settings.motors {
+            rocket.rocket_motor.add_group(
+                IgnitionTrigger::Command,
+                vec![(name.clone(), RocketMotor::new(motor))],
+            );
+        }
+
+        rocket.imu = Some(Imu::new(
+            settings.imu_gyro_read_speed,
+            settings.imu_accel_read_speed,
+        ));
+        rocket.barometer = Some(Barometer::new(settings.baro_read_speed));
+        rocket.gps = Some(Gps::new(settings.gps_read_speed));
+        rocket
+    }
+
+    /// the rigid body being flown, to log where it is
+    pub fn body(&self) -> &PhysicsBody {
+        &self.body
+    }
+
+    /// the tvc mount, where it actually is rather than what it was told
+    pub fn tvc(&self) -> &TVC {
+        &self.tvc
+    }
+
+    /// how long each update steps the simulation, secs
+    pub fn set_time_step(&mut self, time_step: f32) {
+        self.time_step = time_step;
+    }
+
+    /// restarts the simulation's random numbers from `seed`
+    pub fn set_seed(&mut self, seed: u64) {
+        self.seed = seed;
+        self.rng = StdRng::seed_from_u64(seed);
+    }
+
+    /// the seed the simulation's random numbers started from, to put in the log
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// the simulation's random numbers, for anything that needs them (like sensor noise)
+    pub fn rng(&mut self) -> &mut StdRng {
+        &mut self.rng
+    }
+
+    /// lights the next group of motors (see [`RocketEngineSystem::fire`]) at `time`
+    pub fn fire(&mut self, time: f32) {
+        self.rocket_motor.fire(time, &mut self.rng);
+    }
+
     pub fn get_time_seconds(&self) -> f32 {
         self.time
     }
@@ -50,10 +181,163 @@ impl RocketBody {
         self.time * 1000000.0
     }
 
+    /// put the rocket on a launch rail where it is now
+    pub fn set_rail(&mut self, rail: LaunchRail) {
+        self.body.set_rail(rail);
+    }
+
+    /// when and how fast the rocket left the launch rail, the velocity is in rocketengine's frame (z up)
+    pub fn rail_exit(&self) -> Option<RailExit> {
+        self.body.rail_exit()
+    }
+
+    /// mount an imu in the rocket, it is read as the rocket flies
+    pub fn set_imu(&mut self, imu: Imu) {
+        self.imu = Some(imu);
+    }
+
+    /// the imu, with its latest readings
+    pub fn imu(&self) -> Option<&Imu> {
+        self.imu.as_ref()
+    }
+
+    pub fn set_barometer(&mut self, barometer: Barometer) {
+        self.barometer = Some(barometer);
+    }
+
+    /// the barometer, with its latest reading
+    pub fn barometer(&self) -> Option<&Barometer> {
+        self.barometer.as_ref()
+    }
+
+    pub fn set_gps(&mut self, gps: Gps) {
+        self.gps = Some(gps);
+    }
+
+    /// the gps, with the latest fix to get through
+    pub fn gps(&self) -> Option<&Gps> {
+        self.gps.as_ref()
+    }
+
+    /// estimate the altitude from the imu and barometer (which both need to be set) as the rocket flies
+    pub fn set_kalman(&mut self, kalman: Kalman) {
+        self.kalman = Some(kalman);
+    }
+
+    pub fn kalman(&self) -> Option<&Kalman> {
+        self.kalman.as_ref()
+    }
+
+    /// the kalman filter's estimate against the truth, every step
+    pub fn estimation_errors(&self) -> &[EstimationError] {
+        &self.estimation_errors
+    }
+
+    /// estimate the attitude from the imu (which needs to be set), calibrating it while the rocket sits on the pad
+    pub fn set_attitude_estimator(&mut self, estimator: AttitudeEstimator) {
+        self.attitude = Some(estimator);
+    }
+
+    pub fn attitude_estimator(&self) -> Option<&AttitudeEstimator> {
+        self.attitude.as_ref()
+    }
+
+    /// which way the flight code thinks the rocket is pointing, body to world. the real rotation without an
+    /// attitude estimator
+    pub fn attitude(&self) -> Quaternion {
+        self.attitude.as_ref().map_or(self.body.rotation, |estimator| estimator.estimate())
+    }
+
+    /// fly the rocket with `computer`, stepped `rate` times a second with the latest sensor readings. it moves the
+    /// tvc mount, drives the reaction wheel and fires the motors
+    pub fn set_flight_computer(&mut self, computer: impl FlightComputer + Send + 'static, rate: f32) {
+        self.flight_computer = Some(FlightComputerRunner::new(computer, rate));
+    }
+
+    pub fn flight_computer(&self) -> Option<&FlightComputerRunner> {
+        self.flight_computer.as_ref()
+    }
+
+    /// the latest reading from every sensor that is set
+    pub fn sensor_data(&self) -> SensorData {
+        SensorData {
+            time: self.time,
+            gyro: self.imu.as_ref().and_then(|imu| imu.gyro.latest()),
+            accel: self.imu.as_ref().and_then(|imu| imu.accel.latest()),
+            baro: self.barometer.as_ref().and_then(|baro| baro.reading()),
+            gps: self.gps.as_ref().and_then(|gps| gps.reading()),
+            reaction_wheel_speed: self.reaction_wheel.as_ref().map(|wheel| wheel.speed()),
+        }
+    }
+
+    /// roll the rocket with a reaction wheel, driven by the flight computer's `reaction_wheel_output`. its inertia
+    /// should already be in the rocket's mmoi
+    pub fn set_reaction_wheel(&mut self, wheel: ReactionWheel) {
+        self.reaction_wheel = Some(wheel);
+    }
+
+    pub fn reaction_wheel(&self) -> Option<&ReactionWheel> {
+        self.reaction_wheel.as_ref()
+    }
+
+    /// mass without the motors, kg
+    pub fn set_dry_mass(&mut self, mass: f32) {
+        self.dry_mass = mass;
+    }
+
+    /// the motors, to add them and light them
+    pub fn rocket_motor(&self) -> &RocketEngineSystem {
+        &self.rocket_motor
+    }
+
+    pub fn rocket_motor_mut(&mut self) -> &mut RocketEngineSystem {
+        &mut self.rocket_motor
+    }
+
+    /// add a parachute or streamer, for dual deploy add a drogue deployed at apogee and a main deployed at an altitude
+    pub fn add_recovery(&mut self, device: RecoveryDevice) {
+        self.recovery.push(device);
+    }
+
+    /// the ejection charges that have fired so far, with how fast the rocket was going when they did (in rocketengine's
+    /// frame, z up)
+    pub fn ejections(&self) -> &[Ejection] {
+        &self.ejections
+    }
+
+    /// every event so far, in the order they happened
+    pub fn events(&self) -> &[Event] {
+        &self.events
+    }
+
+    /// the events that happened since the last time this was called
+    pub fn poll_events(&mut self) -> impl Iterator<Item = &Event> {
+        let start = self.polled_events;
+        self.polled_events = self.events.len();
+        self.events[start..].iter()
+    }
+
+    /// calls `listener` with every event as it happens
+    pub fn on_event(&mut self, listener: impl FnMut(&Event) + Send + 'static) {
+        self.listeners.push(Box::new(listener));
+    }
+
+    pub fn landed(&self) -> bool {
+        self.detector.landed()
+    }
+
     pub fn update(&mut self) {
+        let sensors = self.sensor_data();
+        if let Some(data) = self.flight_computer.as_mut().and_then(|runner| runner.update(&sensors)) {
+            self.tvc_position = data.tvc_position;
+            self.reaction_wheel_torque = data.reaction_wheel_output;
+            if !data.motor_fire.is_empty() {
+                self.rocket_motor.ignite(data.motor_fire.clone(), self.time, &mut self.rng);
+            }
+        }
+        self.rocket_motor.update(self.time, &mut self.rng);
         self.body.mass = self.dry_mass + self.rocket_motor.total_mass();
 
-        self.rocket_motor.update(self.time);
         self.tvc.actuate(self.tvc_position, self.time_step);
 
         self.tvc
@@ -63,14 +347,368 @@ impl RocketBody {
             Vec3::new(0.0, self.tvc.force.y, self.tvc.force.z) * self.tvc_location.x,
         );
 
+        if let Some(wheel) = &mut self.reaction_wheel {
+            let torque = wheel.update(
+                self.reaction_wheel_torque,
+                self.body.rotational_velocity_local,
+                self.time_step,
+            );
+            self.body.add_torque_local(torque);
+        }
+
         self.body.update_aero();
-        self.body.add_force(self.body.drag_force);
         self.body
-            .add_torque(Vec3::new(0.0, self.body.drag_force.y, self.body.drag_force.z) * -0.15);
+            .add_torque(Vec3::new(0.0, self.body.drag_force.y, self.body.drag_force.z) * self.cp_location.x);
+        self.body.recovery_drag_area = self
+            .recovery
+            .iter()
+            .map(|device| device.drag_area(self.time as f64) as f32)
+            .sum();
+
+        let on_rail = self.body.rail_exit().is_none();
+        let before = (self.time as f64, self.body.position.x as f64);
+        let velocity_before = self.body.velocity;
+        let rotation_before = self.body.rotation;
         self.body.update(self.time_step);
+
+        let mut events = self.rocket_motor.events(self.time, self.time + self.time_step);
+        for event in &events {
+            if let FlightEvent::Ejection(name) = &event.event {
+                let frac = (event.time as f32 - self.time) / self.time_step;
+                let velocity = velocity_before + (self.body.velocity - velocity_before) * frac;
+                // along the rocket and with its mass when the charge fires, like rocketengine does
+                let rotation = rotation_before * (rotation_before.conj() * self.body.rotation).fractional(frac);
+                let mass = self.dry_mass + self.rocket_motor.mass_at(event.time as f32);
+                let impulse = self.rocket_motor.motor(name).map_or(0.0, |m| m.ejection_impulse());
+                self.body.velocity += rotation.rotate(Vec3::new(impulse / mass, 0.0, 0.0));
+                self.ejections.push(Ejection {
+                    motor: name.clone(),
+                    time: event.time,
+                    velocity: velocity.to_rocketengine(),
+                });
+            }
+        }
+        if let Some(imu) = &mut self.imu {
+            imu.update(&self.body, &mut self.rng);
+        }
+        let baro_reading = match &mut self.barometer {
+            Some(barometer) => barometer
+                .update(&self.body, self.rocket_motor.current_thrust() > 0.0, &mut self.rng)
+                .and(barometer.altitude()),
+            None => None,
+        };
+        if let (Some(estimator), Some(imu)) = (&mut self.attitude, &self.imu) {
+            if !self.detector.lifted_off() && self.rocket_motor.current_thrust() == 0.0 {
+                estimator.calibrate(imu.angular_velocity(), imu.acceleration());
+            } else {
+                estimator.update(imu.angular_velocity(), imu.acceleration(), self.time_step);
+            }
+        }
+        let attitude = self.attitude();
+        if let (Some(kalman), Some(imu)) = (&mut self.kalman, &self.imu) {
+            let accel = attitude.rotate(imu.acceleration()) + self.body.gravity_at(self.body.position);
+            kalman.predict(accel.x, self.time_step);
+            if let Some(altitude) = baro_reading {
+                kalman.update_altitude(altitude);
+            }
+            self.estimation_errors.push(EstimationError {
+                time: self.body.time,
+                position: kalman.position(),
+                velocity: kalman.velocity(),
+                true_position: self.body.position.x,
+                true_velocity: self.body.velocity.x,
+            });
+        }
+        if let Some(gps) = &mut self.gps {
+            gps.update(&self.body, &mut self.rng);
+        }
+        if let (true, Some(exit)) = (on_rail, self.body.rail_exit()) {
+            events.push(Event {
+                time: exit.time,
+                event: FlightEvent::RailExit,
+            });
+        }
+        self.time += self.time_step;
+
+        let air = self.body.atmosphere.at(self.body.position.x as f64);
+        let airspeed = (self.body.velocity - self.body.wind).norm() as f64;
+        events.extend(self.detector.update(FlightSample {
+            time: self.time as f64,
+            altitude: self.body.position.x as f64,
+            vertical_velocity: self.body.velocity.x as f64,
+            dynamic_pressure: 0.5 * air.density * airspeed.powi(2),
+        }));
+
+        let start = self.events.len();
+        self.events.extend(events);
+        let after = (self.time as f64, self.body.position.x as f64);
+        for device in &mut self.recovery {
+            if let Some(deploy) = device.update(&self.events, before, after) {
+                self.events.push(deploy);
+            }
+        }
+
+        self.events[start..].sort_by(|a, b| a.time.total_cmp(&b.time));
+        for event in &self.events[start..] {
+            self.listeners.iter_mut().for_each(|listener| listener(event));
+        }
+    }
+
+    /// changes the rocket by a monte carlo sample, before it is flown
+    ///
+    /// the launch angle is only changed if the rocket is on a launch rail
+    pub fn disperse(&mut self, sample: &Sample) {
+        self.dry_mass *= sample.mass_scale as f32;
+        self.body.drag_coefficient *= sample.drag_scale as f32;
+        self.body.wind = Vec3::from_rocketengine(sample.wind(self.body.wind.to_rocketengine()));
+        self.rocket_motor.scale_impulse(sample.impulse_scale as f32);
+        if let Some(rail) = self.body.rail() {
+            self.body.set_rail(sample.rail(rail));
+        } else {
+            // nothing to tilt the rocket off the pad, so it starts tilted instead
+            self.body.rotation = Quaternion::from_rocketengine(sample.tilt()) * self.body.rotation;
+        }
+    }
+
+    /// fires the motors after `ignition_delay` and flies until the rocket lands (or `max_time`), for
+    /// [`MonteCarlo::run_with`](rocketengine::dispersion::MonteCarlo::run_with)
+    pub fn fly(&mut self, ignition_delay: f32, max_time: f32) -> Outcome {
+        self.fire(self.time + ignition_delay);
+        let mut apogee: f64 = 0.0;
+        let mut max_velocity: f64 = 0.0;
+        while !self.landed() && self.time < max_time {
+            self.update();
+            self.clear();
+            apogee = apogee.max(self.body.position.x as f64);
+            max_velocity = max_velocity.max(self.body.velocity.norm() as f64);
+        }
+        let landing = self.body.position.to_rocketengine();
+        Outcome {
+            apogee,
+            max_velocity,
+            landing: rocketengine::math::F64x3::new(landing.x, landing.y, 0.0),
+            landed: self.landed(),
+        }
     }
 
     pub fn clear(&mut self) {
         self.body.clear();
     }
 }
+
+#[test]
+fn test_dispersion() {
+    use rocketengine::dispersion::MonteCarlo;
+    use rocketengine::motor::MotorCatalog;
+
+    use crate::motor::RocketMotor;
+
+    let mc = MonteCarlo::new(5, 7);
+    let results = mc.run_with(|sample| {
+        let mut rocket = RocketBody::new();
+        rocket.set_seed(sample.run as u64);
+        rocket.set_time_step(0.01);
+        rocket.set_rail(LaunchRail::new(1.0));
+        rocket
+            .rocket_motor_mut()
+            .add_motor("main".into(), RocketMotor::new(MotorCatalog::builtin().find("E12").unwrap()));
+        rocket.disperse(sample);
+        rocket.fly(sample.ignition_delay as f32, 120.0)
+    });
+    assert_eq!(results.landings().len(), 5);
+    let apogee = results.apogee();
+    // the default rocket is a heavy 1kg, so an E12 does not get it far
+    assert!(apogee.mean > 5.0);
+    assert!(apogee.std_dev > 0.0);
+}
+
+#[test]
+fn test_seeded() {
+    use rocketengine::motor::MotorCatalog;
+
+    use crate::motor::RocketMotor;
+
+    let fly = |seed: u64| {
+        let mut rocket = RocketBody::new();
+        rocket.set_seed(seed);
+        rocket.set_time_step(0.01);
+        *rocket.rocket_motor_mut() = RocketEngineSystem::new(0.5);
+        rocket
+            .rocket_motor_mut()
+            .add_motor("main".into(), RocketMotor::new(MotorCatalog::builtin().find("E12").unwrap()));
+        rocket.fly(0.0, 120.0)
+    };
+    // the same seed flies exactly the same, down to the last bit
+    assert_eq!(fly(1), fly(1));
+    assert_ne!(fly(1), fly(2));
+}
+
+#[test]
+fn test_ejection() {
+    use rocketengine::motor::MotorCatalog;
+
+    use crate::motor::RocketMotor;
+
+    let mut rocket = RocketBody::new();
+    rocket.set_time_step(0.001);
+    rocket.set_dry_mass(0.3);
+    *rocket.rocket_motor_mut() = RocketEngineSystem::new(0.0);
+    rocket.rocket_motor_mut().add_motor(
+        "main".into(),
+        RocketMotor::new(MotorCatalog::builtin().find("E12").unwrap()).with_ejection(1.0, -0.5),
+    );
+    rocket.fly(0.0, 30.0);
+
+    let ejection = &rocket.ejections()[0];
+    let burnout = rocket.rocket_motor().motor("main").unwrap().burnout_time().unwrap();
+    assert_eq!(ejection.motor, "main");
+    assert!((ejection.time - (burnout + 1.0) as f64).abs() < 1e-3);
+    // still on the way up, a second after burnout
+    assert!(ejection.velocity.z > 0.0);
+    assert!(rocket.events().iter().any(|e| e.time == ejection.time));
+}
+
+#[test]
+fn test_kalman_in_flight() {
+    use rocketengine::motor::MotorCatalog;
+
+    use crate::motor::RocketMotor;
+
+    let mut rocket = RocketBody::new();
+    rocket.set_time_step(0.001);
+    rocket.set_dry_mass(0.3);
+    *rocket.rocket_motor_mut() = RocketEngineSystem::new(0.5);
+    rocket
+        .rocket_motor_mut()
+        .add_motor("main".into(), RocketMotor::new(MotorCatalog::builtin().find("E12").unwrap()));
+    rocket.set_imu(Imu::new(500.0, 500.0));
+    rocket.set_barometer(Barometer::new(40.0));
+    rocket.set_kalman(Kalman::new());
+    let outcome = rocket.fly(0.0, 30.0);
+
+    // until it hits the ground, which is far past what the accelerometer can read
+    let errors: Vec<_> = rocket.estimation_errors().iter().filter(|e| e.true_position > 0.0).collect();
+    assert!(!errors.is_empty());
+    let worst = errors.iter().map(|e| e.position_error().abs()).fold(0.0, f32::max);
+    let worst_velocity = errors.iter().map(|e| e.velocity_error().abs()).fold(0.0, f32::max);
+    assert!(worst < 0.05 * outcome.apogee as f32);
+    assert!(worst_velocity < 3.0);
+}
+
+#[test]
+fn test_attitude_in_flight() {
+    use rocketengine::motor::MotorCatalog;
+
+    use crate::control::AttitudeFilter;
+    use crate::motor::RocketMotor;
+
+    for filter in [AttitudeFilter::Gyro, AttitudeFilter::mahony(), AttitudeFilter::mekf()] {
+        let mut rocket = RocketBody::new();
+        rocket.set_time_step(0.001);
+        rocket.set_dry_mass(0.3);
+        *rocket.rocket_motor_mut() = RocketEngineSystem::new(0.5);
+        rocket
+            .rocket_motor_mut()
+            .add_motor("main".into(), RocketMotor::new(MotorCatalog::builtin().find("E12").unwrap()));
+        rocket.set_rail(LaunchRail::new(1.0).elevation(80f64.to_radians()));
+        rocket.set_imu(Imu::new(500.0, 500.0));
+        rocket.set_attitude_estimator(AttitudeEstimator::new(filter));
+        // a second on the pad to calibrate
+        rocket.fly(1.0, 1.0);
+        let mut worst: f32 = 0.0;
+        while !rocket.detector.reached_apogee() {
+            rocket.update();
+            rocket.clear();
+            if !rocket.detector.lifted_off() {
+                continue;
+            }
+            let up = Vec3::new(1.0, 0.0, 0.0);
+            let nose = rocket.attitude().rotate(up).angle_between(rocket.body.rotation.rotate(up));
+            worst = worst.max(nose);
+        }
+        assert!(worst < 1f32.to_radians());
+    }
+}
+
+#[test]
+fn test_flight_computer() {
+    use rocketengine::motor::MotorCatalog;
+
+    use crate::flight_code::{TvcController, TvcFlightComputer};
+    use crate::motor::RocketMotor;
+
+    let mut rocket = RocketBody::new();
+    rocket.set_time_step(0.001);
+    rocket.set_dry_mass(0.3);
+    *rocket.rocket_motor_mut() = RocketEngineSystem::new(0.0);
+    rocket
+        .rocket_motor_mut()
+        .add_motor("main".into(), RocketMotor::new(MotorCatalog::builtin().find("E12").unwrap()));
+    rocket.set_rail(LaunchRail::new(1.0).elevation(85f64.to_radians()));
+    rocket.set_imu(Imu::new(500.0, 500.0));
+    let computer = TvcFlightComputer::new(TvcController::fsf(20.0, 5.0), 0.35, Vec3::new(0.05, 0.05, 0.05), 0.4)
+        .fire_at("main".into(), 0.5);
+    rocket.set_flight_computer(computer, 100.0);
+
+    // the flight computer lights the motor, and steers as it goes
+    while rocket.time < 2.0 {
+        rocket.update();
+        rocket.clear();
+    }
+    let ignition = rocket.rocket_motor().motor("main").unwrap().ignition_time().unwrap();
+    assert!((ignition - 0.5).abs() < 0.011);
+    assert!(rocket.body.position.x > 1.0);
+    // leaning over 5 degrees towards +z, so it tries to turn back about y
+    assert!(rocket.tvc_position.y != 0.0);
+    assert_eq!(rocket.flight_computer().unwrap().output().tvc_position, rocket.tvc_position);
+}
+
+#[test]
+fn test_reaction_wheel() {
+    use crate::data::ControllData;
+
+    // stops the rocket rolling, with the gyro
+    struct RollDamper;
+    impl FlightComputer for RollDamper {
+        fn step(&mut self, sensors: &SensorData) -> ControllData {
+            ControllData {
+                reaction_wheel_output: sensors.gyro.map_or(0.0, |gyro| -0.5 * gyro.value.x),
+                ..Default::default()
+            }
+        }
+    }
+
+    let mut rocket = RocketBody::new();
+    rocket.set_time_step(0.001);
+    rocket.body.moment_of_inertia = Vec3::new(0.05, 0.05, 0.05);
+    rocket.body.rotational_velocity = Vec3::new(1.0, 0.0, 0.0);
+    rocket.set_imu(Imu::new(500.0, 500.0));
+    rocket.set_reaction_wheel(ReactionWheel::new(0.001, 0.05, 100.0));
+    rocket.set_flight_computer(RollDamper, 100.0);
+    while rocket.time < 2.0 {
+        rocket.update();
+        rocket.clear();
+    }
+    // the wheel soaked up the rocket's roll
+    assert!(rocket.body.rotational_velocity.x.abs() < 0.05);
+    let wheel = rocket.reaction_wheel().unwrap();
+    assert!((wheel.momentum().x + rocket.body.rotational_velocity.x * 0.05 - 0.05).abs() < 1e-3);
+    assert_eq!(rocket.sensor_data().reaction_wheel_speed, Some(wheel.speed()));
+}
+
+#[test]
+fn test_disperse_tilt() {
+    use rocketengine::dispersion::Sample;
+
+    use crate::math::utils::DEG_TO_RAD;
+
+    let sample = Sample {
+        launch_tilt: 5.0 * DEG_TO_RAD as f64,
+        ..Sample::nominal(0)
+    };
+    // without a rail the rocket itself starts tilted
+    let mut rocket = RocketBody::new();
+    rocket.disperse(&sample);
+    let up = Vec3::new(1.0, 0.0, 0.0);
+    assert!((rocket.body.rotation.rotate(up).angle_between(up) - 5.0 * DEG_TO_RAD).abs() < 1e-4);
+}
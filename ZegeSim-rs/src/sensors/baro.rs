@@ -0,0 +1,228 @@
+//! barometer (and the altitude it gives)
+
+use rand::Rng;
+
+use super::{quantize, Reading, SampleClock};
+use crate::math::gaussian;
+use crate::physics::PhysicsBody;
+
+/// everything that makes a barometer read something other than the air pressure around the rocket
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BaroErrors {
+    /// standard deviation of the white noise on each reading, Pa
+    pub noise: f32,
+    /// smallest step between readings, Pa (0 for none)
+    pub resolution: f32,
+    /// time constant of the lag from the air getting through the vent holes into the av bay, secs
+    pub lag: f32,
+    /// the error near mach 1 from the shock passing the vent holes, as a fraction of the dynamic pressure. negative
+    /// reads low, so the altitude jumps up
+    pub transonic: f32,
+    /// the error when the motor burns out and the flow around the rocket changes, as a fraction of the dynamic
+    /// pressure at burnout. negative reads low, which has fooled plenty of altimeters into thinking it is apogee
+    pub burnout: f32,
+    /// how long the burnout error takes to die away (time constant), secs
+    pub burnout_decay: f32,
+}
+
+impl BaroErrors {
+    /// a perfect barometer
+    pub fn none() -> Self {
+        Self {
+            noise: 0.0,
+            resolution: 0.0,
+            lag: 0.0,
+            transonic: 0.0,
+            burnout: 0.0,
+            burnout_decay: 0.0,
+        }
+    }
+}
+
+impl Default for BaroErrors {
+    /// about what a cheap mems barometer in a normal av bay gives
+    fn default() -> Self {
+        Self {
+            noise: 2.0,
+            resolution: 1.0,
+            lag: 0.05,
+            transonic: -0.1,
+            burnout: -0.05,
+            burnout_decay: 0.2,
+        }
+    }
+}
+
+/// a barometer, read at a fixed rate
+#[derive(Debug, Clone, PartialEq)]
+pub struct Barometer {
+    pub errors: BaroErrors,
+    clock: SampleClock,
+    /// the pressure inside the av bay, lagging the air outside
+    inside: Option<f32>,
+    last_time: f32,
+    /// when the motor burnt out, and the dynamic pressure then
+    burnout: Option<(f32, f32)>,
+    burning: bool,
+    /// the first reading, taken sitting on the pad
+    ground: Option<f32>,
+    reading: Option<Reading<f32>>,
+}
+
+impl Barometer {
+    /// `rate` in hz (`baroSpeed` in the settings)
+    pub fn new(rate: f32) -> Self {
+        Self {
+            errors: BaroErrors::default(),
+            clock: SampleClock::new(rate),
+            inside: None,
+            last_time: 0.0,
+            burnout: None,
+            burning: false,
+            ground: None,
+            reading: None,
+        }
+    }
+
+    pub fn errors(mut self, errors: BaroErrors) -> Self {
+        self.errors = errors;
+        self
+    }
+
+    /// the latest reading, pressure in Pa
+    pub fn reading(&self) -> Option<Reading<f32>> {
+        self.reading
+    }
+
+    /// height (m) above where the first reading was taken, from the latest reading
+    pub fn altitude(&self) -> Option<f32> {
+        Some(pressure_altitude(self.reading?.value, self.ground?))
+    }
+
+    /// follows the air around `body` (call this every step so the lag is right), taking a reading if one is due.
+    /// `burning` is if the motors are burning, to know when they burn out
+    pub fn update(
+        &mut self,
+        body: &PhysicsBody,
+        burning: bool,
+        rng: &mut impl Rng,
+    ) -> Option<Reading<f32>> {
+        let time = body.time;
+        let air = body.atmosphere.at(body.position.x as f64);
+        let airspeed = (body.velocity - body.wind).norm();
+        let dynamic_pressure = 0.5 * air.density as f32 * airspeed.powi(2);
+        let mach = airspeed / air.speed_of_sound as f32;
+        let e = self.errors;
+
+        if self.burning && !burning {
+            self.burnout = Some((time, dynamic_pressure));
+        }
+        self.burning = burning;
+
+        // the shock sweeping past the vent holes, worst right at mach 1
+        let mut outside = air.pressure as f32
+            + e.transonic * dynamic_pressure * (-((mach - 1.0) / 0.1).powi(2)).exp();
+        if let Some((burnout_time, burnout_pressure)) = self.burnout {
+            let decay = if e.burnout_decay > 0.0 {
+                (-(time - burnout_time) / e.burnout_decay).exp()
+            } else {
+                0.0
+            };
+            outside += e.burnout * burnout_pressure * decay;
+        }
+
+        let dt = time - self.last_time;
+        self.last_time = time;
+        let inside = match self.inside {
+            Some(inside) if e.lag > 0.0 => {
+                inside + (outside - inside) * (1.0 - (-dt / e.lag).exp())
+            }
+            _ => outside,
+        };
+        self.inside = Some(inside);
+
+        if !self.clock.due(time) {
+            return None;
+        }
+        let pressure = quantize(inside + gaussian(rng, e.noise), e.resolution);
+        self.ground.get_or_insert(pressure);
+        self.reading = Some(Reading {
+            time,
+            value: pressure,
+        });
+        self.reading
+    }
+}
+
+/// height (m) above where the pressure was `ground` (Pa), the way altimeters work it out (the standard troposphere)
+pub fn pressure_altitude(pressure: f32, ground: f32) -> f32 {
+    const LAPSE_RATE: f32 = 0.0065;
+    const SEA_LEVEL_TEMPERATURE: f32 = 288.15;
+    // g / (R * lapse rate)
+    const EXPONENT: f32 = 5.255_88;
+    SEA_LEVEL_TEMPERATURE / LAPSE_RATE * (1.0 - (pressure / ground).powf(1.0 / EXPONENT))
+}
+
+#[test]
+fn test_baro() {
+    use crate::math::Vec3;
+    use rand::SeedableRng;
+    let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+
+    let mut body = PhysicsBody::new();
+    let mut baro = Barometer::new(10.0).errors(BaroErrors {
+        resolution: 1.0,
+        ..BaroErrors::none()
+    });
+    baro.update(&body, false, &mut rng);
+    assert_eq!(baro.reading().unwrap().value, 101325.0);
+    assert_eq!(baro.altitude(), Some(0.0));
+
+    // only reads at its rate
+    body.time = 0.05;
+    body.position = Vec3::new(100.0, 0.0, 0.0);
+    assert!(baro.update(&body, false, &mut rng).is_none());
+    body.time = 0.1;
+    let reading = baro.update(&body, false, &mut rng).unwrap();
+    assert_eq!(reading.time, 0.1);
+    assert!((baro.altitude().unwrap() - 100.0).abs() < 1.0);
+
+    // lags behind the air outside
+    let mut baro = Barometer::new(10.0).errors(BaroErrors {
+        lag: 0.1,
+        ..BaroErrors::none()
+    });
+    body.time = 0.0;
+    body.position = Vec3::default();
+    baro.update(&body, false, &mut rng);
+    body.time = 0.1;
+    body.position = Vec3::new(100.0, 0.0, 0.0);
+    baro.update(&body, false, &mut rng);
+    let altitude = baro.altitude().unwrap();
+    assert!(altitude > 50.0 && altitude < 70.0);
+
+    // at mach 1 it reads low (so high), and burning out makes it read low for a bit
+    let errors = BaroErrors {
+        transonic: -0.1,
+        burnout: -0.05,
+        burnout_decay: 0.2,
+        ..BaroErrors::none()
+    };
+    let mut baro = Barometer::new(1000.0).errors(errors);
+    body.time = 0.0;
+    body.position = Vec3::default();
+    body.velocity = Vec3::new(340.3, 0.0, 0.0);
+    let q = 0.5 * 1.225 * 340.3f32.powi(2);
+    let reading = baro.update(&body, true, &mut rng).unwrap();
+    assert!((reading.value - (101325.0 - 0.1 * q)).abs() < 10.0);
+    body.velocity = Vec3::new(100.0, 0.0, 0.0);
+    body.time = 0.01;
+    baro.update(&body, true, &mut rng);
+    body.time = 0.02;
+    let q = 0.5 * 1.225 * 100f32.powi(2);
+    let reading = baro.update(&body, false, &mut rng).unwrap();
+    assert!((reading.value - (101325.0 - 0.05 * q)).abs() < 1.0);
+    body.time = 2.0;
+    let reading = baro.update(&body, false, &mut rng).unwrap();
+    assert!((reading.value - 101325.0).abs() < 1.0);
+}
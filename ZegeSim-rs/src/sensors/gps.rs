@@ -0,0 +1,215 @@
+//! gps receiver
+
+use std::collections::VecDeque;
+
+use rand::Rng;
+
+use super::{Reading, SampleClock};
+use crate::math::{gaussian, Vec3};
+use crate::physics::PhysicsBody;
+
+/// everything that makes a gps read something other than where the rocket is
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GpsErrors {
+    /// standard deviation of the horizontal position noise, m
+    pub horizontal_noise: f32,
+    /// standard deviation of the vertical position noise, m (always worse than horizontal)
+    pub vertical_noise: f32,
+    /// standard deviation of the velocity noise, m/s
+    pub velocity_noise: f32,
+    /// how long after a fix is taken it gets to the flight computer, secs
+    pub latency: f32,
+    /// the fix is lost going faster than this, m/s (most receivers give up past 515, the cocom limit)
+    pub max_speed: f32,
+    /// the fix is lost higher than this, m (18km for the cocom limit, though most receivers only need one of them)
+    pub max_altitude: f32,
+    /// how long it takes to get a fix back once it was lost, secs
+    pub reacquire_time: f32,
+}
+
+impl GpsErrors {
+    /// a perfect gps
+    pub fn none() -> Self {
+        Self {
+            horizontal_noise: 0.0,
+            vertical_noise: 0.0,
+            velocity_noise: 0.0,
+            latency: 0.0,
+            max_speed: f32::INFINITY,
+            max_altitude: f32::INFINITY,
+            reacquire_time: 0.0,
+        }
+    }
+}
+
+impl Default for GpsErrors {
+    /// about what a cheap gps module gives
+    fn default() -> Self {
+        Self {
+            horizontal_noise: 2.5,
+            vertical_noise: 5.0,
+            velocity_noise: 0.1,
+            latency: 0.1,
+            max_speed: 515.0,
+            max_altitude: 18_000.0,
+            reacquire_time: 1.0,
+        }
+    }
+}
+
+/// where the gps thinks the rocket is, in the same frame as the simulation (x up)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GpsFix {
+    /// m from the launch site
+    pub position: Vec3,
+    /// m/s
+    pub velocity: Vec3,
+}
+
+/// a gps receiver, giving fixes at a fixed rate
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gps {
+    pub errors: GpsErrors,
+    clock: SampleClock,
+    /// fixes that have been taken but have not got to the flight computer yet
+    pending: VecDeque<Reading<GpsFix>>,
+    /// when the fix was lost, and when it was got back (if it has been)
+    lost: Option<(f32, Option<f32>)>,
+    reading: Option<Reading<GpsFix>>,
+}
+
+impl Gps {
+    /// `rate` in hz (`gpsSpeed` in the settings)
+    pub fn new(rate: f32) -> Self {
+        Self {
+            errors: GpsErrors::default(),
+            clock: SampleClock::new(rate),
+            pending: VecDeque::new(),
+            lost: None,
+            reading: None,
+        }
+    }
+
+    pub fn errors(mut self, errors: GpsErrors) -> Self {
+        self.errors = errors;
+        self
+    }
+
+    /// the latest fix to get to the flight computer, timed from when it was taken (so it is `latency` old)
+    pub fn reading(&self) -> Option<Reading<GpsFix>> {
+        self.reading
+    }
+
+    /// if the receiver has a fix right now
+    pub fn has_fix(&self) -> bool {
+        self.lost.is_none()
+    }
+
+    /// takes a fix of `body` if one is due, returning the fix that gets to the flight computer now (if any)
+    pub fn update(&mut self, body: &PhysicsBody, rng: &mut impl Rng) -> Option<Reading<GpsFix>> {
+        let time = body.time;
+        let e = self.errors;
+
+        let too_fast = body.velocity.norm() > e.max_speed || body.position.x > e.max_altitude;
+        self.lost = match self.lost {
+            _ if too_fast => Some((time, None)),
+            Some((lost, None)) => Some((lost, Some(time + e.reacquire_time))),
+            Some((_, Some(reacquired))) if time >= reacquired => None,
+            lost => lost,
+        };
+
+        if self.clock.due(time) && self.has_fix() {
+            let noise = Vec3::new(
+                gaussian(rng, e.vertical_noise),
+                gaussian(rng, e.horizontal_noise),
+                gaussian(rng, e.horizontal_noise),
+            );
+            let velocity_noise = Vec3::new(
+                gaussian(rng, e.velocity_noise),
+                gaussian(rng, e.velocity_noise),
+                gaussian(rng, e.velocity_noise),
+            );
+            self.pending.push_back(Reading {
+                time,
+                value: GpsFix {
+                    position: body.position + noise,
+                    velocity: body.velocity + velocity_noise,
+                },
+            });
+        }
+
+        let mut arrived = None;
+        while let Some(fix) = self
+            .pending
+            .front()
+            .filter(|fix| fix.time + e.latency <= time)
+        {
+            arrived = Some(*fix);
+            self.pending.pop_front();
+        }
+        if arrived.is_some() {
+            self.reading = arrived;
+        }
+        arrived
+    }
+}
+
+#[test]
+fn test_gps() {
+    use rand::SeedableRng;
+    let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+
+    let errors = GpsErrors {
+        latency: 0.1,
+        max_speed: 100.0,
+        reacquire_time: 0.5,
+        ..GpsErrors::none()
+    };
+    let mut gps = Gps::new(10.0).errors(errors);
+    let mut body = PhysicsBody::new();
+    body.position = Vec3::new(0.0, 1.0, 2.0);
+
+    // the fix shows up after the latency
+    assert!(gps.update(&body, &mut rng).is_none());
+    body.time = 0.05;
+    assert!(gps.update(&body, &mut rng).is_none());
+    body.time = 0.1;
+    body.position = Vec3::new(10.0, 1.0, 2.0);
+    let fix = gps.update(&body, &mut rng).unwrap();
+    assert_eq!(fix.time, 0.0);
+    assert_eq!(fix.value.position, Vec3::new(0.0, 1.0, 2.0));
+
+    // too fast, the fix is lost until it has slowed down for long enough
+    body.velocity = Vec3::new(200.0, 0.0, 0.0);
+    body.time = 0.2;
+    gps.update(&body, &mut rng);
+    assert!(!gps.has_fix());
+    body.velocity = Vec3::new(50.0, 0.0, 0.0);
+    for step in 3..=7 {
+        body.time = step as f32 / 10.0;
+        gps.update(&body, &mut rng);
+    }
+    assert!(!gps.has_fix());
+    body.time = 0.8;
+    gps.update(&body, &mut rng);
+    assert!(gps.has_fix());
+    // nothing taken while it had no fix got through
+    body.time = 0.85;
+    assert!(gps.update(&body, &mut rng).is_none());
+    assert_eq!(gps.reading().unwrap().time, 0.1);
+
+    // noisy, worse vertically
+    let mut gps = Gps::new(1000.0).errors(GpsErrors::default());
+    let errors: Vec<Vec3> = (0..2000)
+        .filter_map(|i| {
+            body.time = i as f32 / 1000.0;
+            gps.update(&body, &mut rng)
+        })
+        .map(|fix| fix.value.position - Vec3::new(10.0, 1.0, 2.0))
+        .collect();
+    let spread = |axis: fn(&Vec3) -> f32| {
+        (errors.iter().map(|e| axis(e).powi(2)).sum::<f32>() / errors.len() as f32).sqrt()
+    };
+    assert!((spread(|e| e.x) - 5.0).abs() < 0.5);
+    assert!((spread(|e| e.y) - 2.5).abs() < 0.25);
+}
@@ -0,0 +1,279 @@
+//! gyroscope and accelerometer
+
+use rand::Rng;
+
+use super::{quantize, Reading, SampleClock};
+use crate::math::{gaussian, Vec3};
+use crate::physics::PhysicsBody;
+
+/// everything that makes a three axis sensor read something other than the truth
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ImuErrors {
+    /// standard deviation of the white noise on each reading
+    pub noise: f32,
+    /// constant offset on each axis
+    pub bias: Vec3,
+    /// how fast the bias wanders (a random walk), standard deviation per sqrt(second)
+    pub bias_instability: f32,
+    /// error in the scale of each axis, 0.01 reads 1% high
+    pub scale_factor: Vec3,
+    /// small rotation (radians about x, y and z) of the sensor's axes from the body's
+    pub misalignment: Vec3,
+    /// largest reading the sensor can give, anything past it is clipped
+    pub range: f32,
+    /// smallest step between readings, 0 for none
+    pub resolution: f32,
+}
+
+impl ImuErrors {
+    /// a perfect sensor
+    pub fn none() -> Self {
+        Self {
+            noise: 0.0,
+            bias: Vec3::default(),
+            bias_instability: 0.0,
+            scale_factor: Vec3::default(),
+            misalignment: Vec3::default(),
+            range: f32::INFINITY,
+            resolution: 0.0,
+        }
+    }
+
+    /// about what a cheap mems gyro gives (rad/s), set to +-2000 deg/s with a 16 bit output
+    pub fn gyro() -> Self {
+        let range = 2000f32.to_radians();
+        Self {
+            noise: 0.003,
+            bias_instability: 0.0002,
+            range,
+            resolution: range / 32768.0,
+            ..Self::none()
+        }
+    }
+
+    /// about what a cheap mems accelerometer gives (m/s^2), set to +-16g with a 16 bit output
+    pub fn accel() -> Self {
+        let range = 16.0 * 9.80665;
+        Self {
+            noise: 0.02,
+            bias_instability: 0.0005,
+            range,
+            resolution: range / 32768.0,
+            ..Self::none()
+        }
+    }
+}
+
+/// one three axis sensor (a gyroscope or an accelerometer), read at a fixed rate
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImuSensor {
+    pub errors: ImuErrors,
+    clock: SampleClock,
+    /// how far the bias has wandered
+    drift: Vec3,
+    last_sample: Option<f32>,
+    reading: Vec3,
+}
+
+impl ImuSensor {
+    /// `rate` in hz
+    pub fn new(rate: f32, errors: ImuErrors) -> Self {
+        Self {
+            errors,
+            clock: SampleClock::new(rate),
+            drift: Vec3::default(),
+            last_sample: None,
+            reading: Vec3::default(),
+        }
+    }
+
+    /// the latest reading, held between samples
+    pub fn reading(&self) -> Vec3 {
+        self.reading
+    }
+
+    /// the latest reading and when it was taken, None before the first
+    pub fn latest(&self) -> Option<Reading<Vec3>> {
+        self.last_sample.map(|time| Reading {
+            time,
+            value: self.reading,
+        })
+    }
+
+    /// takes a reading of `truth` (in the body frame) if one is due at `time`, returning it if it was
+    pub fn sample(&mut self, time: f32, truth: Vec3, rng: &mut impl Rng) -> Option<Vec3> {
+        if !self.clock.due(time) {
+            return None;
+        }
+        let e = self.errors;
+        let dt = self.last_sample.map_or(0.0, |last| time - last);
+        self.last_sample = Some(time);
+        self.drift += Vec3::new(
+            gaussian(rng, e.bias_instability),
+            gaussian(rng, e.bias_instability),
+            gaussian(rng, e.bias_instability),
+        ) * dt.sqrt();
+
+        // small angle rotation onto the sensor's axes
+        let sensed = truth + e.misalignment.cross(truth);
+        let sensed = sensed * (Vec3::new(1.0, 1.0, 1.0) + e.scale_factor) + e.bias + self.drift;
+        let noise = Vec3::new(
+            gaussian(rng, e.noise),
+            gaussian(rng, e.noise),
+            gaussian(rng, e.noise),
+        );
+        let read = |v: f32| quantize(v.clamp(-e.range, e.range), e.resolution);
+        let sensed = sensed + noise;
+        self.reading = Vec3::new(read(sensed.x), read(sensed.y), read(sensed.z));
+        Some(self.reading)
+    }
+}
+
+/// a gyroscope and accelerometer, mounted somewhere in the rocket
+#[derive(Debug, Clone, PartialEq)]
+pub struct Imu {
+    /// rad/s
+    pub gyro: ImuSensor,
+    /// m/s^2, what the accelerometer feels (so 1g up sitting on the pad)
+    pub accel: ImuSensor,
+    /// where the imu is from the center of mass, m in the body frame. when the rocket spins this adds centripetal (and
+    /// tangential) acceleration
+    pub offset: Vec3,
+    /// the body frame angular velocity at the last update, to find the angular acceleration
+    last_rotation: Option<(f32, Vec3)>,
+}
+
+impl Imu {
+    /// an imu with typical errors, at the center of mass. rates are in hz (`gyroSpeed` and `accelSpeed` in the settings)
+    pub fn new(gyro_rate: f32, accel_rate: f32) -> Self {
+        Self {
+            gyro: ImuSensor::new(gyro_rate, ImuErrors::gyro()),
+            accel: ImuSensor::new(accel_rate, ImuErrors::accel()),
+            offset: Vec3::default(),
+            last_rotation: None,
+        }
+    }
+
+    pub fn offset(mut self, offset: Vec3) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// what the gyro reads (rad/s, body frame)
+    pub fn angular_velocity(&self) -> Vec3 {
+        self.gyro.reading()
+    }
+
+    /// what the accelerometer reads (m/s^2, body frame)
+    pub fn acceleration(&self) -> Vec3 {
+        self.accel.reading()
+    }
+
+    /// samples both sensors if they are due, from the state of `body` after it was updated
+    pub fn update(&mut self, body: &PhysicsBody, rng: &mut impl Rng) {
+        let time = body.time;
+        let w = body.rotational_velocity_local;
+        let alpha = match self.last_rotation {
+            Some((last_time, last)) if time > last_time => (w - last) / (time - last_time),
+            _ => Vec3::default(),
+        };
+        self.last_rotation = Some((time, w));
+
+        // an accelerometer feels everything but gravity
+        let specific_force = body
+            .rotation
+            .conj()
+            .rotate(body.acceleration - body.gravity_at(body.position));
+        let r = self.offset;
+        let at_imu = specific_force + alpha.cross(r) + w.cross(w.cross(r));
+
+        self.gyro.sample(time, w, rng);
+        self.accel.sample(time, at_imu, rng);
+    }
+}
+
+#[test]
+fn test_imu_errors() {
+    use rand::SeedableRng;
+    let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+
+    // only takes readings at its rate
+    let mut sensor = ImuSensor::new(100.0, ImuErrors::none());
+    assert!(sensor
+        .sample(0.0, Vec3::new(1.0, 0.0, 0.0), &mut rng)
+        .is_some());
+    assert!(sensor
+        .sample(0.005, Vec3::new(2.0, 0.0, 0.0), &mut rng)
+        .is_none());
+    assert_eq!(sensor.reading(), Vec3::new(1.0, 0.0, 0.0));
+    assert!(sensor
+        .sample(0.01, Vec3::new(2.0, 0.0, 0.0), &mut rng)
+        .is_some());
+
+    let errors = ImuErrors {
+        bias: Vec3::new(0.1, 0.0, 0.0),
+        scale_factor: Vec3::new(0.5, 0.0, 0.0),
+        range: 10.0,
+        resolution: 0.25,
+        ..ImuErrors::none()
+    };
+    let mut sensor = ImuSensor::new(100.0, errors);
+    let read = sensor
+        .sample(0.0, Vec3::new(1.0, 100.0, 0.3), &mut rng)
+        .unwrap();
+    // 1 * 1.5 + 0.1, rounded to the nearest 0.25
+    assert_eq!(read.x, 1.5);
+    // saturated
+    assert_eq!(read.y, 10.0);
+    assert_eq!(read.z, 0.25);
+
+    // a misaligned sensor sees a bit of the other axes
+    let errors = ImuErrors {
+        misalignment: Vec3::new(0.0, 0.0, 0.01),
+        ..ImuErrors::none()
+    };
+    let read = ImuSensor::new(100.0, errors)
+        .sample(0.0, Vec3::new(1.0, 0.0, 0.0), &mut rng)
+        .unwrap();
+    assert!((read.y - 0.01).abs() < 1e-6);
+
+    // the noise has about the right spread
+    let mut sensor = ImuSensor::new(
+        1000.0,
+        ImuErrors {
+            noise: 0.5,
+            ..ImuErrors::none()
+        },
+    );
+    let readings: Vec<f32> = (0..4000)
+        .filter_map(|i| sensor.sample(i as f32 / 1000.0, Vec3::default(), &mut rng))
+        .map(|r| r.x)
+        .collect();
+    let std_dev = (readings.iter().map(|r| r * r).sum::<f32>() / readings.len() as f32).sqrt();
+    assert!((std_dev - 0.5).abs() < 0.05);
+}
+
+#[test]
+fn test_imu_on_pad() {
+    use rand::SeedableRng;
+    let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+
+    let mut body = PhysicsBody::new();
+    body.mass = 1.0;
+    let mut imu = Imu::new(100.0, 100.0);
+    imu.gyro.errors = ImuErrors::none();
+    imu.accel.errors = ImuErrors::none();
+    body.update(0.01);
+    imu.update(&body, &mut rng);
+    // sitting still, the floor pushes up at 1g
+    assert!((imu.acceleration().x - 9.80665).abs() < 1e-3);
+    assert_eq!(imu.angular_velocity(), Vec3::default());
+
+    // spinning about the long axis, an imu off to the side feels pulled outwards
+    body.rotational_velocity = Vec3::new(10.0, 0.0, 0.0);
+    body.rotational_velocity_local = body.rotational_velocity;
+    body.time += 0.01;
+    let mut imu = imu.offset(Vec3::new(0.0, 0.05, 0.0));
+    imu.update(&body, &mut rng);
+    assert!((imu.acceleration().y + 100.0 * 0.05).abs() < 1e-3);
+}
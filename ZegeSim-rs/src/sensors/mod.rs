@@ -0,0 +1,52 @@
+//! simulated sensors, which see the rocket the way the real ones on the flight computer would (noisy, slow, and a bit wrong)
+
+pub mod baro;
+pub mod gps;
+pub mod imu;
+
+pub use baro::{BaroErrors, Barometer};
+pub use gps::{Gps, GpsErrors, GpsFix};
+pub use imu::{Imu, ImuErrors, ImuSensor};
+
+/// something a sensor measured, and when (secs) it was measured
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Reading<T> {
+    pub time: f32,
+    pub value: T,
+}
+
+/// decides when a sensor running at a fixed rate takes its next reading
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SampleClock {
+    /// hz
+    pub rate: f32,
+    next: f32,
+}
+
+impl SampleClock {
+    pub fn new(rate: f32) -> Self {
+        Self { rate, next: 0.0 }
+    }
+
+    /// if a reading is due at `time`, moving on to the next one if it is
+    pub fn due(&mut self, time: f32) -> bool {
+        if time < self.next {
+            return false;
+        }
+        self.next += 1.0 / self.rate;
+        if self.next <= time {
+            // skips readings that were missed, instead of taking them all at once
+            self.next = time + 1.0 / self.rate;
+        }
+        true
+    }
+}
+
+/// rounds `value` to the nearest step of `resolution` (none if it is 0)
+pub fn quantize(value: f32, resolution: f32) -> f32 {
+    if resolution > 0.0 {
+        (value / resolution).round() * resolution
+    } else {
+        value
+    }
+}
@@ -27,6 +27,8 @@ def load_motor_file(file_name):
         "name": metadata.get("code"),
         "dry_weight": float(metadata.get("initWt")) - float(metadata.get("propWt")),
         "fuel_weight": float(metadata.get("propWt")),
+        # ejection delays the motor is sold with, P (plugged) is left out
+        "delays": [float(d) for d in metadata.get("delays", "").split(",") if d.strip() and d.strip().upper() != "P"],
     }
 
 def interpolate_thrust(thrust_curve, timeStep):
@@ -69,7 +71,7 @@ config = settings["cfg"]
 interpolation_step = config["interp_step"]
 
 manifest = ""
-for file in cwd.joinpath("raw_thrust_profiles").iterdir():
+for file in sorted(cwd.joinpath("raw_thrust_profiles").glob("*.rse")):
     data = load_motor_file(file)
     res = ""
     res += f"pub const DATA: [[f64; 3]; {len(data['data'])}] = [\n"
@@ -79,6 +81,7 @@ for file in cwd.joinpath("raw_thrust_profiles").iterdir():
     res += f"pub const DRY_WEIGHT: f64 = {data['dry_weight']};\n"
     res += f"pub const FUEL_WEIGHT: f64 = {data['fuel_weight']};\n"
     res += f"pub const MOTOR_ID: &str = \"{data['name']}\";\n"
+    res += f"pub const DELAYS: [f64; {len(data['delays'])}] = [{', '.join(str(d) for d in data['delays'])}];\n"
     manifest += f"#[allow(non_snake_case)] pub mod {data['name']};\n"
     
     with open(f"src/motor/raw/{data['name']}.rs", "w") as f:
@@ -3,8 +3,13 @@ use std::path::PathBuf;
 use std::env;
 
 fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    // motors are loaded at runtime now, the generated constants in src/motor/raw are only rebuilt on request
+    if env::var_os("CARGO_FEATURE_CODEGEN").is_none() {
+        return;
+    }
     println!("cargo:rerun-if-changed=raw_thrust_profiles");
-    println!("cargo:rerun-if-changed=motors_build_config.yaml");
+    println!("cargo:rerun-if-changed=config/motors_build_config.yaml");
     println!("cargo:rerun-if-changed=build-motors.py");
     let here = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
     let output = Command::new("python")
@@ -13,6 +18,6 @@ fn main() {
         .output()
         .expect("Running rocket motor parser script");
     if !output.status.success() {
-        println!("{}", format!("cargo:warning={:?}", output));
+        println!("cargo:warning={:?}", output);
     }
 }
@@ -0,0 +1,163 @@
+//! aerodynamic forces
+
+use crate::atmosphere::Air;
+use crate::math::F64x3;
+
+/// drag coefficient of the rocket
+#[derive(Debug, Clone, PartialEq)]
+pub enum DragCoefficient {
+    Constant(f64),
+    /// mach number and drag coefficient pairs, sorted by mach number.
+    /// linearly interpolated between, and held at the ends
+    Mach(Vec<[f64; 2]>),
+}
+
+impl Default for DragCoefficient {
+    fn default() -> Self {
+        Self::Constant(0.0)
+    }
+}
+
+impl DragCoefficient {
+    /// the drag coefficient at the given mach number
+    pub fn at(&self, mach: f64) -> f64 {
+        match self {
+            Self::Constant(cd) => *cd,
+            Self::Mach(table) => {
+                let i = table.partition_point(|p| p[0] <= mach);
+                match (i.checked_sub(1).map(|i| table[i]), table.get(i)) {
+                    (Some(a), Some(b)) => a[1] + (b[1] - a[1]) * (mach - a[0]) / (b[0] - a[0]),
+                    (Some(last), None) => last[1],
+                    (None, Some(first)) => first[1],
+                    (None, None) => 0.0,
+                }
+            }
+        }
+    }
+
+    /// the same coefficient (or table), multiplied by `scale`
+    pub fn scaled(&self, scale: f64) -> Self {
+        match self {
+            Self::Constant(cd) => Self::Constant(cd * scale),
+            Self::Mach(table) => Self::Mach(table.iter().map(|&[mach, cd]| [mach, cd * scale]).collect()),
+        }
+    }
+}
+
+impl From<f64> for DragCoefficient {
+    fn from(cd: f64) -> Self {
+        Self::Constant(cd)
+    }
+}
+
+impl From<Vec<[f64; 2]>> for DragCoefficient {
+    fn from(mut table: Vec<[f64; 2]>) -> Self {
+        table.sort_by(|a, b| a[0].total_cmp(&b[0]));
+        Self::Mach(table)
+    }
+}
+
+/// the drag model of a rocket
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Drag {
+    /// m^2, usually the cross section of the body tube. an area of 0 is no drag at all
+    pub reference_area: f64,
+    pub coefficient: DragCoefficient,
+    /// normal force coefficient slope (CN alpha, per radian), how hard the air pushes the rocket sideways when it flies
+    /// at an angle of attack. 0 is no normal force
+    pub normal_coefficient: f64,
+    /// center of pressure, where the aerodynamic forces act, m from the center of mass in the body frame. behind the
+    /// center of mass (-z) the rocket turns into the wind
+    pub cp: F64x3,
+}
+
+impl Drag {
+    pub fn new(reference_area: f64, coefficient: impl Into<DragCoefficient>) -> Self {
+        Self {
+            reference_area,
+            coefficient: coefficient.into(),
+            ..Self::default()
+        }
+    }
+
+    pub fn normal_coefficient(mut self, normal_coefficient: f64) -> Self {
+        self.normal_coefficient = normal_coefficient;
+        self
+    }
+
+    pub fn cp(mut self, cp: F64x3) -> Self {
+        self.cp = cp;
+        self
+    }
+
+    /// drag force (newtons) on something moving at `air_velocity` relative to the `air` around it
+    pub fn force(&self, air_velocity: F64x3, air: &Air) -> F64x3 {
+        let speed = air_velocity.norm();
+        if speed == 0.0 || self.reference_area == 0.0 {
+            return F64x3::zero();
+        }
+        let cd = self.coefficient.at(speed / air.speed_of_sound);
+        // 1/2 * rho * v^2 * cd * A, pointing against the velocity
+        air_velocity * (-0.5 * air.density * speed * cd * self.reference_area)
+    }
+
+    /// normal force (newtons, body frame) on a rocket moving at `air_velocity` (body frame) relative to the air,
+    /// pushing it back towards flying straight into the air
+    pub fn normal_force(&self, air_velocity: F64x3, air: &Air) -> F64x3 {
+        let sideways = F64x3::new(air_velocity.x, air_velocity.y, 0.0);
+        if sideways.norm() == 0.0 || self.reference_area == 0.0 {
+            return F64x3::zero();
+        }
+        let aoa = sideways.norm().atan2(air_velocity.z);
+        let speed = air_velocity.norm();
+        let force = 0.5 * air.density * speed * speed * self.reference_area * self.normal_coefficient * aoa.sin();
+        sideways * (-force / sideways.norm())
+    }
+
+    /// torque (nm, body frame) about the center of mass from an aerodynamic force (body frame) acting at the cp
+    pub fn torque(&self, force: F64x3) -> F64x3 {
+        self.cp.cross(force)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::atmosphere::Atmosphere;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn mach_table() {
+        let cd = DragCoefficient::from(vec![[1.0, 0.8], [0.0, 0.4], [2.0, 0.6]]);
+        assert_approx_eq!(cd.at(-1.0), 0.4);
+        assert_approx_eq!(cd.at(0.5), 0.6);
+        assert_approx_eq!(cd.at(1.0), 0.8);
+        assert_approx_eq!(cd.at(1.5), 0.7);
+        assert_approx_eq!(cd.at(3.0), 0.6);
+    }
+
+    #[test]
+    fn drag_force() {
+        let air = Atmosphere::standard().at(0.0);
+        let drag = Drag::new(0.01, 0.5);
+        let force = drag.force(F64x3::new(0.0, 0.0, -10.0), &air);
+        assert_approx_eq!(force.x, 0.0);
+        assert_approx_eq!(force.z, 0.5 * 1.225 * 100.0 * 0.5 * 0.01, 1e-5);
+        assert_eq!(Drag::default().force(F64x3::new(1.0, 2.0, 3.0), &air), F64x3::zero());
+    }
+
+    #[test]
+    fn normal_force() {
+        let air = Atmosphere::standard().at(0.0);
+        let drag = Drag::new(0.01, 0.5).normal_coefficient(2.0).cp(F64x3::new(0.0, 0.0, -0.2));
+        // flying straight, nothing sideways
+        assert_eq!(drag.normal_force(F64x3::new(0.0, 0.0, 10.0), &air), F64x3::zero());
+        // 10 degrees off, with the air coming from +x
+        let aoa = 10.0f64.to_radians();
+        let force = drag.normal_force(F64x3::new(aoa.sin(), 0.0, aoa.cos()) * 10.0, &air);
+        assert_approx_eq!(force.x, -0.5 * 1.225 * 100.0 * 0.01 * 2.0 * aoa.sin(), 1e-5);
+        assert_approx_eq!(force.z, 0.0);
+        // pushing the tail towards -x turns the nose towards +x, into the air
+        assert!(drag.torque(force).y > 0.0);
+    }
+}
@@ -0,0 +1,154 @@
+//! the US standard atmosphere (1976), up to 86km
+//!
+//! see <https://ntrs.nasa.gov/citations/19770009539>. above 86km the last layer is just continued, which is not
+//! accurate but the air is thin enough there that it does not matter for anything this simulates
+
+/// radius of the earth used to convert geometric to geopotential altitude, m
+const EARTH_RADIUS: f64 = 6_356_766.0;
+/// specific gas constant of air, J/(kg*K)
+const GAS_CONSTANT: f64 = 287.052_87;
+/// ratio of specific heats of air
+const GAMMA: f64 = 1.4;
+/// g0 * M / R*, used in the pressure equations. K/m
+const GMR: f64 = 9.806_65 / GAS_CONSTANT;
+
+/// base geopotential altitude (m), lapse rate (K/m), base temperature (K), and base pressure (Pa) of each layer
+const LAYERS: [[f64; 4]; 8] = [
+    [0.0, -0.0065, 288.15, 101_325.0],
+    [11_000.0, 0.0, 216.65, 22_632.06],
+    [20_000.0, 0.001, 216.65, 5_474.889],
+    [32_000.0, 0.0028, 228.65, 868.018_7],
+    [47_000.0, 0.0, 270.65, 110.906_3],
+    [51_000.0, -0.0028, 270.65, 66.938_87],
+    [71_000.0, -0.002, 214.65, 3.956_42],
+    // 86km geometric
+    [84_852.0, 0.0, 186.946, 0.373_4],
+];
+
+/// the air at some altitude
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Air {
+    /// K
+    pub temperature: f64,
+    /// Pa
+    pub pressure: f64,
+    /// kg/m^3
+    pub density: f64,
+    /// m/s
+    pub speed_of_sound: f64,
+}
+
+/// temperature and pressure of the standard atmosphere at a geometric altitude above sea level
+fn standard(altitude: f64) -> (f64, f64) {
+    let h = EARTH_RADIUS * altitude / (EARTH_RADIUS + altitude);
+    let [base, lapse, base_temp, base_pressure] = LAYERS[LAYERS.partition_point(|l| l[0] <= h).max(1) - 1];
+    let temperature = base_temp + lapse * (h - base);
+    let pressure = if lapse == 0.0 {
+        base_pressure * (-GMR * (h - base) / base_temp).exp()
+    } else {
+        base_pressure * (base_temp / temperature).powf(GMR / lapse)
+    };
+    (temperature, pressure)
+}
+
+/// the atmosphere around a launch site
+///
+/// altitudes are measured from the launch site, and the measured ground temperature and pressure (if they are set)
+/// shift the whole standard atmosphere to match them
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Atmosphere {
+    /// height of the launch site above sea level, m
+    elevation: f64,
+    /// K
+    ground_temperature: Option<f64>,
+    /// Pa
+    ground_pressure: Option<f64>,
+}
+
+impl Atmosphere {
+    /// the standard atmosphere, launching from sea level
+    pub fn standard() -> Self {
+        Self::default()
+    }
+
+    /// height of the launch site above sea level, m
+    pub fn elevation(mut self, elevation: f64) -> Self {
+        self.elevation = elevation;
+        self
+    }
+
+    /// temperature measured at the launch site, K
+    pub fn ground_temperature(mut self, temperature: f64) -> Self {
+        self.ground_temperature = Some(temperature);
+        self
+    }
+
+    /// pressure measured at the launch site, Pa
+    pub fn ground_pressure(mut self, pressure: f64) -> Self {
+        self.ground_pressure = Some(pressure);
+        self
+    }
+
+    pub fn site_elevation(&self) -> f64 {
+        self.elevation
+    }
+
+    /// the air at a height (m) above the launch site
+    pub fn at(&self, height: f64) -> Air {
+        let (ground_temp, ground_pressure) = standard(self.elevation);
+        let temp_offset = self.ground_temperature.map_or(0.0, |t| t - ground_temp);
+        let pressure_scale = self.ground_pressure.map_or(1.0, |p| p / ground_pressure);
+
+        let (temperature, pressure) = standard(self.elevation + height);
+        let temperature = temperature + temp_offset;
+        let pressure = pressure * pressure_scale;
+        Air {
+            temperature,
+            pressure,
+            density: pressure / (GAS_CONSTANT * temperature),
+            speed_of_sound: (GAMMA * GAS_CONSTANT * temperature).sqrt(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn standard_table() {
+        let atmosphere = Atmosphere::standard();
+        // values from the 1976 tables, at geometric altitudes
+        for (altitude, temperature, pressure, density) in [
+            (0.0, 288.15, 101_325.0, 1.225),
+            (1_000.0, 281.651, 89_874.6, 1.111_64),
+            (11_000.0, 216.774, 22_699.9, 0.364_801),
+            (25_000.0, 221.552, 2_549.22, 0.040_084),
+            (50_000.0, 270.65, 79.778_7, 0.001_026_9),
+            (80_000.0, 198.639, 1.052_4, 0.000_018_458),
+            // the molecular scale temperature, the kinetic temperature is a bit lower this high up
+            (86_000.0, 186.946, 0.373_38, 0.000_006_958),
+        ] {
+            let air = atmosphere.at(altitude);
+            assert_approx_eq!(air.temperature, temperature, 0.01);
+            assert_approx_eq!(air.pressure / pressure, 1.0, 1e-3);
+            assert_approx_eq!(air.density / density, 1.0, 2e-3);
+        }
+        assert_approx_eq!(atmosphere.at(0.0).speed_of_sound, 340.294, 1e-3);
+    }
+
+    #[test]
+    fn launch_site() {
+        let site = Atmosphere::standard().elevation(1_000.0);
+        assert_eq!(site.at(0.0), Atmosphere::standard().at(1_000.0));
+
+        let hot = site.ground_temperature(303.15).ground_pressure(90_000.0);
+        let ground = hot.at(0.0);
+        assert_approx_eq!(ground.temperature, 303.15);
+        assert_approx_eq!(ground.pressure, 90_000.0);
+        // the offset carries up with the rest of the atmosphere
+        assert_approx_eq!(hot.at(500.0).temperature - site.at(500.0).temperature, 303.15 - 281.651, 0.01);
+        assert!(hot.at(0.0).density < site.at(0.0).density);
+    }
+}
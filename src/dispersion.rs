@@ -0,0 +1,499 @@
+//! monte carlo dispersion analysis, to see where a rocket could land (and how high it could go) given how
+//! much everything about it and the weather might vary
+//!
+//! each run draws a [`Sample`] from the [`Dispersion`], flies it, and keeps the [`Outcome`]. the runs are drawn
+//! from a seeded generator, so the same seed always gives the same results
+
+use std::f64::consts::PI;
+use std::fmt;
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::math::{F64x3, Quaternion};
+use crate::rail::LaunchRail;
+use crate::rocket::RocketFactory;
+use crate::secs;
+
+/// how much each input varies between runs
+///
+/// everything except the ignition delay is a standard deviation of a normal distribution around the nominal value
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Dispersion {
+    /// fraction of the dry mass (0.02 is 2%)
+    pub mass: f64,
+    /// fraction of the drag coefficient
+    pub drag: f64,
+    /// m/s
+    pub wind_speed: f64,
+    /// radians
+    pub wind_direction: f64,
+    /// how far (radians) the rocket is tilted from the nominal launch angle, in a random direction
+    pub launch_angle: f64,
+    /// fraction of the motors' total impulse
+    pub impulse: f64,
+    /// the longest the motors take to light, secs. the delay is uniform between 0 and this
+    pub ignition_delay: secs,
+}
+
+impl Default for Dispersion {
+    /// roughly what can be expected at a club launch
+    fn default() -> Self {
+        Self {
+            mass: 0.02,
+            drag: 0.05,
+            wind_speed: 1.0,
+            wind_direction: 10.0 * PI / 180.0,
+            launch_angle: 1.0 * PI / 180.0,
+            impulse: 0.03,
+            ignition_delay: 0.5,
+        }
+    }
+}
+
+/// the values drawn for a single run
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sample {
+    pub run: usize,
+    /// multiplies the dry mass
+    pub mass_scale: f64,
+    /// multiplies the drag coefficient
+    pub drag_scale: f64,
+    /// added to the nominal wind speed, m/s
+    pub wind_speed: f64,
+    /// added to the nominal wind direction, radians clockwise
+    pub wind_direction: f64,
+    /// radians away from the nominal launch angle
+    pub launch_tilt: f64,
+    /// which way the tilt leans, radians clockwise from north
+    pub launch_tilt_direction: f64,
+    /// multiplies the motors' thrust
+    pub impulse_scale: f64,
+    /// secs after the launch button is pressed that the motors light
+    pub ignition_delay: secs,
+}
+
+/// a normally distributed number, by the Box-Muller transform
+fn normal(rng: &mut impl Rng, std_dev: f64) -> f64 {
+    let u: f64 = 1.0 - rng.gen::<f64>();
+    let v: f64 = rng.gen();
+    std_dev * (-2.0 * u.ln()).sqrt() * (2.0 * PI * v).cos()
+}
+
+impl Sample {
+    /// the nominal rocket, with nothing changed
+    pub fn nominal(run: usize) -> Self {
+        Self {
+            run,
+            mass_scale: 1.0,
+            drag_scale: 1.0,
+            wind_speed: 0.0,
+            wind_direction: 0.0,
+            launch_tilt: 0.0,
+            launch_tilt_direction: 0.0,
+            impulse_scale: 1.0,
+            ignition_delay: 0.0,
+        }
+    }
+
+    pub fn draw(run: usize, dispersion: &Dispersion, rng: &mut impl Rng) -> Self {
+        Self {
+            run,
+            mass_scale: 1.0 + normal(rng, dispersion.mass),
+            drag_scale: (1.0 + normal(rng, dispersion.drag)).max(0.0),
+            wind_speed: normal(rng, dispersion.wind_speed),
+            wind_direction: normal(rng, dispersion.wind_direction),
+            launch_tilt: normal(rng, dispersion.launch_angle).abs(),
+            launch_tilt_direction: rng.gen::<f64>() * 2.0 * PI,
+            impulse_scale: (1.0 + normal(rng, dispersion.impulse)).max(0.0),
+            ignition_delay: rng.gen::<f64>() * dispersion.ignition_delay,
+        }
+    }
+
+    /// the nominal wind, with the speed and direction changed. the vertical part is kept
+    pub fn wind(&self, nominal: F64x3) -> F64x3 {
+        let speed = (nominal.x.hypot(nominal.y) + self.wind_speed).max(0.0);
+        let direction = nominal.x.atan2(nominal.y) + self.wind_direction;
+        F64x3::new(speed * direction.sin(), speed * direction.cos(), nominal.z)
+    }
+
+    /// the rotation that tilts the rocket (in the simulation frame)
+    pub fn tilt(&self) -> Quaternion {
+        // the axis is horizontal, and square to the direction of the tilt
+        let axis = F64x3::new(-self.launch_tilt_direction.cos(), self.launch_tilt_direction.sin(), 0.0);
+        Quaternion::from_axis_angle(axis, self.launch_tilt)
+    }
+
+    /// the nominal launch rail, tilted
+    pub fn rail(&self, nominal: LaunchRail) -> LaunchRail {
+        let direction = self.tilt().rotate(nominal.direction());
+        nominal
+            .elevation(direction.z.clamp(-1.0, 1.0).asin())
+            .azimuth(direction.x.atan2(direction.y))
+    }
+}
+
+/// what happened in a single run
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Outcome {
+    /// m above the launch site
+    pub apogee: f64,
+    /// m/s
+    pub max_velocity: f64,
+    /// where the rocket came down, m from the launch site (x east, y north)
+    pub landing: F64x3,
+    /// false if the run hit the time limit before landing
+    pub landed: bool,
+}
+
+/// mean, standard deviation and range of something over all the runs
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Stats {
+    pub mean: f64,
+    pub std_dev: f64,
+    pub min: f64,
+    pub max: f64,
+}
+
+impl Stats {
+    pub fn of(values: impl IntoIterator<Item = f64>) -> Self {
+        let values: Vec<f64> = values.into_iter().collect();
+        let n = values.len() as f64;
+        let mean = values.iter().sum::<f64>() / n;
+        let variance = if values.len() > 1 {
+            values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (n - 1.0)
+        } else {
+            0.0
+        };
+        Self {
+            mean,
+            std_dev: variance.sqrt(),
+            min: values.iter().copied().fold(f64::INFINITY, f64::min),
+            max: values.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+        }
+    }
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:.2} +- {:.2} ({:.2} to {:.2})", self.mean, self.std_dev, self.min, self.max)
+    }
+}
+
+/// an ellipse around the landing points, from their covariance
+///
+/// note that in two dimensions a 1 sigma ellipse only holds about 39% of the landings (86% for 2 sigma, and 99% for 3)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ellipse {
+    /// m from the launch site (x east, y north)
+    pub center: F64x3,
+    /// m
+    pub semi_major: f64,
+    pub semi_minor: f64,
+    /// direction of the major axis, radians clockwise from north (0 to pi)
+    pub azimuth: f64,
+}
+
+impl Ellipse {
+    /// the ellipse `sigma` standard deviations out around `points`
+    pub fn of(points: &[F64x3], sigma: f64) -> Self {
+        let n = points.len() as f64;
+        let center = points.iter().fold(F64x3::zero(), |sum, p| sum + *p) / n;
+        let (mut xx, mut yy, mut xy) = (0.0, 0.0, 0.0);
+        if points.len() > 1 {
+            for p in points {
+                let d = *p - center;
+                xx += d.x * d.x;
+                yy += d.y * d.y;
+                xy += d.x * d.y;
+            }
+            xx /= n - 1.0;
+            yy /= n - 1.0;
+            xy /= n - 1.0;
+        }
+        // eigenvalues of the covariance matrix are the variances along the axes
+        let mid = (xx + yy) / 2.0;
+        let spread = (((xx - yy) / 2.0).powi(2) + xy * xy).sqrt();
+        // angle of the major axis from +x (east)
+        let angle = 0.5 * (2.0 * xy).atan2(xx - yy);
+        Self {
+            center: F64x3::new(center.x, center.y, 0.0),
+            semi_major: sigma * (mid + spread).sqrt(),
+            semi_minor: sigma * (mid - spread).max(0.0).sqrt(),
+            azimuth: (PI / 2.0 - angle).rem_euclid(PI),
+        }
+    }
+
+    /// if a point is inside the ellipse
+    pub fn contains(&self, point: F64x3) -> bool {
+        let d = point - self.center;
+        // along and across the major axis
+        let along = d.x * self.azimuth.sin() + d.y * self.azimuth.cos();
+        let across = d.x * self.azimuth.cos() - d.y * self.azimuth.sin();
+        (along / self.semi_major).powi(2) + (across / self.semi_minor).powi(2) <= 1.0
+    }
+}
+
+impl fmt::Display for Ellipse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "center {:.1}m east {:.1}m north, {:.1}m x {:.1}m, major axis {:.0} deg from north",
+            self.center.x,
+            self.center.y,
+            self.semi_major,
+            self.semi_minor,
+            self.azimuth.to_degrees()
+        )
+    }
+}
+
+/// one line of the csv written by [`Results::write_csv`]
+#[derive(Debug, serde::Serialize)]
+struct CSVRow {
+    run: usize,
+    mass_scale: f64,
+    drag_scale: f64,
+    wind_speed: f64,
+    wind_direction: f64,
+    launch_tilt: f64,
+    launch_tilt_direction: f64,
+    impulse_scale: f64,
+    ignition_delay: secs,
+    apogee: f64,
+    max_velocity: f64,
+    landing_x: f64,
+    landing_y: f64,
+    landed: bool,
+}
+
+/// every run of a monte carlo analysis
+#[derive(Debug, Clone, PartialEq)]
+pub struct Results {
+    pub seed: u64,
+    pub runs: Vec<(Sample, Outcome)>,
+}
+
+impl Results {
+    pub fn apogee(&self) -> Stats {
+        Stats::of(self.runs.iter().map(|(_, outcome)| outcome.apogee))
+    }
+
+    pub fn max_velocity(&self) -> Stats {
+        Stats::of(self.runs.iter().map(|(_, outcome)| outcome.max_velocity))
+    }
+
+    /// where the runs that landed came down
+    pub fn landings(&self) -> Vec<F64x3> {
+        self.runs.iter().filter(|(_, o)| o.landed).map(|(_, o)| o.landing).collect()
+    }
+
+    /// the landing ellipse `sigma` standard deviations out
+    pub fn landing_ellipse(&self, sigma: f64) -> Ellipse {
+        Ellipse::of(&self.landings(), sigma)
+    }
+
+    /// writes every run (what was drawn, and what happened) to a csv file, under a `# seed: ` line
+    pub fn write_csv(&self, path: impl AsRef<Path>) -> Result<(), csv::Error> {
+        let mut file = File::create(path)?;
+        writeln!(file, "# seed: {}", self.seed)?;
+        let mut writer = csv::Writer::from_writer(file);
+        for (sample, outcome) in &self.runs {
+            writer.serialize(CSVRow {
+                run: sample.run,
+                mass_scale: sample.mass_scale,
+                drag_scale: sample.drag_scale,
+                wind_speed: sample.wind_speed,
+                wind_direction: sample.wind_direction,
+                launch_tilt: sample.launch_tilt,
+                launch_tilt_direction: sample.launch_tilt_direction,
+                impulse_scale: sample.impulse_scale,
+                ignition_delay: sample.ignition_delay,
+                apogee: outcome.apogee,
+                max_velocity: outcome.max_velocity,
+                landing_x: outcome.landing.x,
+                landing_y: outcome.landing.y,
+                landed: outcome.landed,
+            })?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+impl fmt::Display for Results {
+    /// a summary of the analysis
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let landed = self.landings().len();
+        writeln!(f, "{} runs (seed {}), {} landed", self.runs.len(), self.seed, landed)?;
+        writeln!(f, "apogee (m):         {}", self.apogee())?;
+        writeln!(f, "max velocity (m/s): {}", self.max_velocity())?;
+        if landed > 0 {
+            for sigma in 1..=3 {
+                writeln!(f, "landing {}sigma: {}", sigma, self.landing_ellipse(sigma as f64))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// runs a rocket many times over, with everything varied a bit each time
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MonteCarlo {
+    pub runs: usize,
+    pub seed: u64,
+    pub dispersion: Dispersion,
+    /// secs
+    pub time_step: secs,
+    /// a run that has not landed by now is stopped
+    pub max_time: secs,
+}
+
+impl MonteCarlo {
+    pub fn new(runs: usize, seed: u64) -> Self {
+        Self {
+            runs,
+            seed,
+            dispersion: Dispersion::default(),
+            time_step: 0.01,
+            max_time: 600.0,
+        }
+    }
+
+    pub fn dispersion(mut self, dispersion: Dispersion) -> Self {
+        self.dispersion = dispersion;
+        self
+    }
+
+    pub fn time_step(mut self, time_step: secs) -> Self {
+        self.time_step = time_step;
+        self
+    }
+
+    pub fn max_time(mut self, max_time: secs) -> Self {
+        self.max_time = max_time;
+        self
+    }
+
+    /// the samples for every run, in order
+    pub fn samples(&self) -> Vec<Sample> {
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        (0..self.runs).map(|run| Sample::draw(run, &self.dispersion, &mut rng)).collect()
+    }
+
+    /// flies every sample with `fly`, for anything that is not a [`RocketFactory`]
+    pub fn run_with(&self, mut fly: impl FnMut(&Sample) -> Outcome) -> Results {
+        Results {
+            seed: self.seed,
+            runs: self.samples().into_iter().map(|sample| (sample, fly(&sample))).collect(),
+        }
+    }
+
+    /// flies the rocket from `factory` for every sample, lighting the `ignite` engines after the sample's ignition delay
+    pub fn run(&self, factory: &RocketFactory, ignite: &[&str]) -> Results {
+        self.run_with(|sample| {
+            let mut rocket = factory.dispersed(sample).at(F64x3::zero());
+            for name in ignite {
+                rocket.light_engine_at(name.to_string(), sample.ignition_delay);
+            }
+            let mut apogee: f64 = 0.0;
+            let mut max_velocity: f64 = 0.0;
+            while !rocket.landed() && rocket.time() < self.max_time {
+                rocket.tick(self.time_step);
+                apogee = apogee.max(rocket.position().z);
+                max_velocity = max_velocity.max(rocket.velocity().norm());
+            }
+            Outcome {
+                apogee,
+                max_velocity,
+                landing: F64x3::new(rocket.position().x, rocket.position().y, 0.0),
+                landed: rocket.landed(),
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::integrator::IntegrationMethod;
+    use crate::math::DEG_TO_RAD;
+    use crate::motor::MotorCatalog;
+    use crate::recovery::RecoveryDevice;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn ellipse() {
+        // spread out along a line 45 degrees east of north, with a bit of width
+        let mut rng = StdRng::seed_from_u64(1);
+        let points: Vec<F64x3> = (0..2000)
+            .map(|_| {
+                let (along, across) = (normal(&mut rng, 10.0), normal(&mut rng, 2.0));
+                F64x3::new(along + across, along - across, 0.0) / 2f64.sqrt() + F64x3::new(5.0, 0.0, 0.0)
+            })
+            .collect();
+        let one = Ellipse::of(&points, 1.0);
+        assert_approx_eq!(one.center.x, 5.0, 0.5);
+        assert_approx_eq!(one.semi_major, 10.0, 0.5);
+        assert_approx_eq!(one.semi_minor, 2.0, 0.1);
+        assert_approx_eq!(one.azimuth, 45.0 * DEG_TO_RAD, 0.02);
+        assert_approx_eq!(Ellipse::of(&points, 3.0).semi_major, one.semi_major * 3.0);
+
+        let inside = |sigma: f64| {
+            let ellipse = Ellipse::of(&points, sigma);
+            points.iter().filter(|p| ellipse.contains(**p)).count() as f64 / points.len() as f64
+        };
+        assert_approx_eq!(inside(1.0), 0.39, 0.03);
+        assert_approx_eq!(inside(2.0), 0.86, 0.03);
+    }
+
+    #[test]
+    fn tilt() {
+        let sample = Sample {
+            launch_tilt: 5.0 * DEG_TO_RAD,
+            launch_tilt_direction: 90.0 * DEG_TO_RAD,
+            ..Sample::nominal(0)
+        };
+        let rail = sample.rail(LaunchRail::new(1.0));
+        assert_approx_eq!(rail.elevation, 85.0 * DEG_TO_RAD);
+        assert_approx_eq!(rail.azimuth, 90.0 * DEG_TO_RAD);
+
+        let wind = Sample { wind_speed: 1.0, wind_direction: 90.0 * DEG_TO_RAD, ..Sample::nominal(0) }.wind(F64x3::new(0.0, 2.0, 0.0));
+        assert_approx_eq!(wind.x, 3.0);
+        assert_approx_eq!(wind.y, 0.0);
+    }
+
+    #[test]
+    fn seeded_runs() {
+        let factory = RocketFactory::with_mass(500.0)
+            .add_engine("main".into(), MotorCatalog::builtin().find("E12").unwrap())
+            .drag(0.0015, 0.45)
+            .add_recovery(RecoveryDevice::parachute("chute", 0.5, 1.5))
+            .launch_rail(LaunchRail::new(1.0))
+            .wind(F64x3::new(3.0, 0.0, 0.0))
+            .integrator(IntegrationMethod::RK4);
+        let mc = MonteCarlo::new(20, 42).time_step(0.02);
+        let results = mc.run(&factory, &["main"]);
+        assert_eq!(results.runs.len(), 20);
+        assert_eq!(results, mc.run(&factory, &["main"]));
+        assert_ne!(results.runs, MonteCarlo::new(20, 43).time_step(0.02).run(&factory, &["main"]).runs);
+
+        assert_eq!(results.landings().len(), 20);
+        let apogee = results.apogee();
+        assert!(apogee.std_dev > 0.0 && apogee.min < apogee.mean && apogee.mean < apogee.max);
+        // blown downwind
+        assert!(results.landing_ellipse(1.0).center.x > 0.0);
+        let summary = results.to_string();
+        assert!(summary.contains("landing 3sigma"));
+
+        let csv = std::env::temp_dir().join("rocketengine_seeded_runs.csv");
+        results.write_csv(&csv).unwrap();
+        let written = std::fs::read_to_string(&csv).unwrap();
+        std::fs::remove_file(&csv).unwrap();
+        assert_eq!(written.lines().count(), 22);
+        assert!(written.starts_with("# seed: 42\nrun,mass_scale,"));
+    }
+}
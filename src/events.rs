@@ -0,0 +1,257 @@
+//! detection of the points in a flight that everything else cares about (liftoff, burnout, apogee, ...)
+
+use std::fmt;
+
+use crate::math::F64x3;
+use crate::motor::Motor;
+use crate::secs;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FlightEvent {
+    /// a motor was lit
+    Ignition(String),
+    /// the rocket started going up
+    Liftoff,
+    /// the rocket left the launch rail
+    RailExit,
+    /// a motor ran out of propellant
+    Burnout(String),
+    /// the highest dynamic pressure on the way up
+    MaxQ,
+    Apogee,
+    /// a recovery device was deployed
+    Deploy(String),
+    /// a stage separated from the rest of the rocket
+    Separation(String),
+    /// the ejection charge of a motor fired
+    Ejection(String),
+    /// the rocket came back down to the height it launched from
+    Landing,
+}
+
+impl fmt::Display for FlightEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Ignition(motor) => write!(f, "ignition({})", motor),
+            Self::Liftoff => write!(f, "liftoff"),
+            Self::RailExit => write!(f, "rail_exit"),
+            Self::Burnout(motor) => write!(f, "burnout({})", motor),
+            Self::MaxQ => write!(f, "max_q"),
+            Self::Apogee => write!(f, "apogee"),
+            Self::Deploy(device) => write!(f, "deploy({})", device),
+            Self::Separation(stage) => write!(f, "separation({})", stage),
+            Self::Ejection(motor) => write!(f, "ejection({})", motor),
+            Self::Landing => write!(f, "landing"),
+        }
+    }
+}
+
+/// an event, and when it happened
+#[derive(Debug, Clone, PartialEq)]
+pub struct Event {
+    pub time: secs,
+    pub event: FlightEvent,
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}@{:.4}", self.event, self.time)
+    }
+}
+
+/// when an ejection charge fired, and how fast the rocket was going
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ejection {
+    pub motor: String,
+    pub time: secs,
+    /// m/s, just before the charge fired
+    pub velocity: F64x3,
+}
+
+impl Ejection {
+    pub fn speed(&self) -> f64 {
+        self.velocity.norm()
+    }
+}
+
+/// something that wants to know about events as they happen
+pub type EventListener = Box<dyn FnMut(&Event) + Send>;
+
+/// the state of the rocket at the end of a step, as far as the [`EventDetector`] cares
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FlightSample {
+    pub time: secs,
+    /// height above the launch site, m
+    pub altitude: f64,
+    /// m/s, up is positive
+    pub vertical_velocity: f64,
+    /// 1/2 * air density * airspeed^2, Pa
+    pub dynamic_pressure: f64,
+}
+
+/// finds liftoff, max-Q, apogee, and landing from the samples of a flight, interpolating between samples to get their times
+///
+/// motor events are known exactly by the simulation, so they come from [`motor_events`] instead
+#[derive(Debug, Clone, Default)]
+pub struct EventDetector {
+    /// the last two samples, newest last
+    last: Option<FlightSample>,
+    before_last: Option<FlightSample>,
+    lifted_off: bool,
+    max_q: bool,
+    apogee: bool,
+    landed: bool,
+}
+
+/// where between `a` and `b` a line through them crosses zero, as a fraction of the way from `a` to `b`
+fn zero_crossing(a: f64, b: f64) -> f64 {
+    if a == b {
+        0.0
+    } else {
+        (a / (a - b)).clamp(0.0, 1.0)
+    }
+}
+
+impl EventDetector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// for something that is already flying (like a booster that just separated), so liftoff and max-Q are not looked for
+    pub fn in_flight() -> Self {
+        Self {
+            lifted_off: true,
+            max_q: true,
+            ..Self::default()
+        }
+    }
+
+    pub fn lifted_off(&self) -> bool {
+        self.lifted_off
+    }
+
+    pub fn reached_apogee(&self) -> bool {
+        self.apogee
+    }
+
+    pub fn landed(&self) -> bool {
+        self.landed
+    }
+
+    /// feeds in the state at the end of a step, returning the events that happened during it
+    pub fn update(&mut self, sample: FlightSample) -> Vec<Event> {
+        let mut events = vec![];
+        let Some(last) = self.last else {
+            self.last = Some(sample);
+            return events;
+        };
+        let dt = sample.time - last.time;
+        let crossing = |a: f64, b: f64| last.time + dt * zero_crossing(a, b);
+
+        if !self.lifted_off && sample.vertical_velocity > 0.0 {
+            self.lifted_off = true;
+            events.push(Event {
+                time: crossing(last.vertical_velocity.min(0.0), sample.vertical_velocity),
+                event: FlightEvent::Liftoff,
+            });
+        }
+
+        if self.lifted_off && !self.max_q && !self.apogee {
+            // the last sample was a peak, so fit a parabola through the three around it
+            if let Some(first) = self.before_last {
+                if last.dynamic_pressure > first.dynamic_pressure && last.dynamic_pressure >= sample.dynamic_pressure {
+                    self.max_q = true;
+                    let (t0, t1, t2) = (first.time, last.time, sample.time);
+                    let (q0, q1, q2) = (first.dynamic_pressure, last.dynamic_pressure, sample.dynamic_pressure);
+                    let denom = (t0 - t1) * (t0 - t2) * (t1 - t2);
+                    let a = (t2 * (q1 - q0) + t1 * (q0 - q2) + t0 * (q2 - q1)) / denom;
+                    let b = (t2 * t2 * (q0 - q1) + t1 * t1 * (q2 - q0) + t0 * t0 * (q1 - q2)) / denom;
+                    let time = if a < 0.0 { (-b / (2.0 * a)).clamp(t0, t2) } else { t1 };
+                    events.push(Event { time, event: FlightEvent::MaxQ });
+                }
+            }
+        }
+
+        if self.lifted_off && !self.apogee && last.vertical_velocity > 0.0 && sample.vertical_velocity <= 0.0 {
+            self.apogee = true;
+            events.push(Event {
+                time: crossing(last.vertical_velocity, sample.vertical_velocity),
+                event: FlightEvent::Apogee,
+            });
+        }
+
+        if self.apogee && !self.landed && sample.altitude <= 0.0 {
+            self.landed = true;
+            events.push(Event {
+                time: crossing(last.altitude, sample.altitude),
+                event: FlightEvent::Landing,
+            });
+        }
+
+        self.before_last = self.last;
+        self.last = Some(sample);
+        events
+    }
+}
+
+/// ignition, burnout, and ejection of a motor, if they happened in the step from `t0` to `t1` (including `t0`, but not `t1`)
+pub fn motor_events(name: &str, motor: &Motor, t0: secs, t1: secs) -> Vec<Event> {
+    let mut events = vec![];
+    if let Some(ignition) = motor.ignition_time() {
+        let burnout = ignition + motor.burn_time();
+        if (t0..t1).contains(&ignition) {
+            events.push(Event {
+                time: ignition,
+                event: FlightEvent::Ignition(name.to_string()),
+            });
+        }
+        if (t0..t1).contains(&burnout) {
+            events.push(Event {
+                time: burnout,
+                event: FlightEvent::Burnout(name.to_string()),
+            });
+        }
+    }
+    if let Some(ejection) = motor.ejection_time() {
+        if (t0..t1).contains(&ejection) {
+            events.push(Event {
+                time: ejection,
+                event: FlightEvent::Ejection(name.to_string()),
+            });
+        }
+    }
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn ballistic() {
+        // thrown up at 20m/s at t=1, without any drag
+        let g = 10.0;
+        let mut detector = EventDetector::new();
+        let mut events = vec![];
+        let mut t = 0.0;
+        while t < 6.0 {
+            let (altitude, vertical_velocity) = if t < 1.0 {
+                (0.0, 0.0)
+            } else {
+                let flight = t - 1.0;
+                (20.0 * flight - 0.5 * g * flight * flight, 20.0 - g * flight)
+            };
+            // fastest just as it leaves the ground, and getting slower until apogee
+            let dynamic_pressure = vertical_velocity * vertical_velocity * (1.0 - t / 100.0);
+            events.extend(detector.update(FlightSample { time: t, altitude, vertical_velocity, dynamic_pressure }));
+            t += 0.03;
+        }
+        let names: Vec<String> = events.iter().map(|e| e.event.to_string()).collect();
+        assert_eq!(names, vec!["liftoff", "max_q", "apogee", "landing"]);
+        assert_approx_eq!(events[0].time, 1.0, 0.03);
+        assert_approx_eq!(events[2].time, 3.0, 1e-6);
+        assert_approx_eq!(events[3].time, 5.0, 0.01);
+        assert!(detector.landed());
+    }
+}
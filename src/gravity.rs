@@ -0,0 +1,124 @@
+//! models of earth's gravity
+//!
+//! positions are in the simulation frame: the origin is the launch site, z is up, and y is north
+
+use std::fmt::Debug;
+
+use crate::consts::GRAVITY;
+use crate::math::F64x3;
+
+/// standard gravitational parameter of the earth, m^3/s^2
+pub const EARTH_MU: f64 = 3.986_004_418e14;
+/// mean radius of the earth, m
+pub const EARTH_MEAN_RADIUS: f64 = 6_371_008.8;
+/// equatorial radius of the earth (WGS84), m
+pub const EARTH_EQUATORIAL_RADIUS: f64 = 6_378_137.0;
+/// second zonal harmonic of the earth's gravity field (how much the bulge at the equator pulls)
+pub const EARTH_J2: f64 = 1.082_626_68e-3;
+
+pub trait GravityModel: Debug + Send + Sync {
+    /// acceleration due to gravity at a position (m), in m/s^2
+    fn acceleration(&self, position: F64x3) -> F64x3;
+}
+
+/// the same acceleration everywhere
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Constant(pub F64x3);
+
+impl Default for Constant {
+    /// standard gravity, straight down
+    fn default() -> Self {
+        Self(GRAVITY)
+    }
+}
+
+impl GravityModel for Constant {
+    fn acceleration(&self, _position: F64x3) -> F64x3 {
+        self.0
+    }
+}
+
+/// a spherical earth, gravity falls off with the square of the distance from its center
+///
+/// scaled so that it is standard gravity at sea level
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct InverseSquare {
+    /// height of the launch site above sea level, m
+    pub site_elevation: f64,
+}
+
+impl InverseSquare {
+    pub fn new(site_elevation: f64) -> Self {
+        Self { site_elevation }
+    }
+}
+
+impl GravityModel for InverseSquare {
+    fn acceleration(&self, position: F64x3) -> F64x3 {
+        // from the center of the earth
+        let r = position + F64x3::new(0.0, 0.0, EARTH_MEAN_RADIUS + self.site_elevation);
+        let distance = r.norm();
+        r * (GRAVITY.z * EARTH_MEAN_RADIUS.powi(2) / distance.powi(3))
+    }
+}
+
+/// an oblate earth, including the J2 term for the bulge around the equator
+///
+/// the launch site is still placed on a sphere of the mean radius, and the rotation of the earth is not included
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct J2 {
+    /// latitude of the launch site, radians
+    pub latitude: f64,
+    /// height of the launch site above sea level, m
+    pub site_elevation: f64,
+}
+
+impl J2 {
+    pub fn new(latitude: f64, site_elevation: f64) -> Self {
+        Self { latitude, site_elevation }
+    }
+}
+
+impl GravityModel for J2 {
+    fn acceleration(&self, position: F64x3) -> F64x3 {
+        let r = position + F64x3::new(0.0, 0.0, EARTH_MEAN_RADIUS + self.site_elevation);
+        let distance = r.norm();
+        // the earth's axis, seen from the launch site
+        let axis = F64x3::new(0.0, self.latitude.cos(), self.latitude.sin());
+        let polar = r.dot(axis);
+        let c = 1.5 * EARTH_J2 * (EARTH_EQUATORIAL_RADIUS / distance).powi(2);
+        let radial = 1.0 - c * (5.0 * polar.powi(2) / distance.powi(2) - 1.0);
+        (r * radial + axis * (2.0 * c * polar)) * (-EARTH_MU / distance.powi(3))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn inverse_square() {
+        let sea_level = InverseSquare::default();
+        assert_approx_eq!(sea_level.acceleration(F64x3::zero()).z, GRAVITY.z);
+        // a quarter of the pull one earth radius up
+        assert_approx_eq!(sea_level.acceleration(F64x3::new(0.0, 0.0, EARTH_MEAN_RADIUS)).z, GRAVITY.z / 4.0);
+        // always towards the center of the earth
+        let far = sea_level.acceleration(F64x3::new(100_000.0, 0.0, 0.0));
+        assert!(far.x < 0.0);
+        assert_approx_eq!(InverseSquare::new(1_000.0).acceleration(F64x3::zero()).z, sea_level.acceleration(F64x3::new(0.0, 0.0, 1_000.0)).z);
+    }
+
+    #[test]
+    fn oblate() {
+        let equator = J2::new(0.0, 0.0).acceleration(F64x3::zero());
+        let pole = J2::new(std::f64::consts::FRAC_PI_2, 0.0).acceleration(F64x3::zero());
+        // without the bulge this would be about 9.820 at both
+        assert_approx_eq!(equator.z, -9.836, 1e-3);
+        assert_approx_eq!(pole.z, -9.788, 1e-3);
+        assert_approx_eq!(equator.y, 0.0);
+        // the bulge pulls towards the equator (south)
+        let mid = J2::new(std::f64::consts::FRAC_PI_4, 0.0).acceleration(F64x3::zero());
+        assert!(mid.y < 0.0 && mid.y > -0.05);
+    }
+}
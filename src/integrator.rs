@@ -0,0 +1,289 @@
+//! numerical integrators, used to step the state of a simulation forward in time
+//!
+//! the integrators work on anything implementing [`State`], and get the rate of change of the state from a
+//! closure, so everything that depends on the state (drag, gravity, thrust) is re-evaluated at every stage
+
+use std::fmt::Debug;
+
+use crate::secs;
+
+/// the state of a simulated body. the rate of change of a state is also described with the state type
+/// (eg. the rate of a position and velocity is a velocity and acceleration)
+pub trait State: Clone {
+    /// `self + rate * dt` for every part of the state
+    fn advance(&self, rate: &Self, dt: secs) -> Self;
+
+    /// `self + rate * dt` for only the velocity like parts of the state (the ones whose rate is an acceleration)
+    fn advance_velocity(&self, rate: &Self, dt: secs) -> Self;
+
+    /// moves the position like parts of the state by the velocities in the state
+    fn drift(&self, dt: secs) -> Self;
+
+    /// how far apart two states are, used for error control. this should be the largest difference of any part of the state
+    fn error(&self, other: &Self) -> f64;
+
+    /// fixes up the state after a step (eg. renormalizing quaternions)
+    fn normalized(self) -> Self {
+        self
+    }
+}
+
+/// the rate of change of a state: `rate(t, window, state)`
+///
+/// `window` is how long the rate is going to be used for, starting at `t`. forces that are only known by their impulse
+/// (like motor thrust) should be averaged over it. a window of 0 means the rate at exactly `t`
+pub type Rate<'a, S> = dyn FnMut(secs, secs, &S) -> S + 'a;
+
+pub trait Integrator<S: State>: Debug {
+    /// integrates `state` from `t` to `t + dt`
+    fn integrate(&mut self, state: &S, t: secs, dt: secs, rate: &mut Rate<S>) -> S;
+}
+
+/// semi-implicit (symplectic) euler, the velocity is updated and then the position is moved with the new velocity
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Euler;
+
+impl<S: State> Integrator<S> for Euler {
+    fn integrate(&mut self, state: &S, t: secs, dt: secs, rate: &mut Rate<S>) -> S {
+        let k = rate(t, dt, state);
+        state.advance_velocity(&k, dt).drift(dt).normalized()
+    }
+}
+
+/// classic 4th order runge kutta
+///
+/// every stage asks for the rate at an instant (a window of 0), so a thrust curve is sampled rather than averaged and
+/// the impulse delivered is only as good as the step is small
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RK4;
+
+impl<S: State> Integrator<S> for RK4 {
+    fn integrate(&mut self, state: &S, t: secs, dt: secs, rate: &mut Rate<S>) -> S {
+        let half = dt / 2.0;
+        let k1 = rate(t, 0.0, state);
+        let k2 = rate(t + half, 0.0, &state.advance(&k1, half));
+        let k3 = rate(t + half, 0.0, &state.advance(&k2, half));
+        let k4 = rate(t + dt, 0.0, &state.advance(&k3, dt));
+        state
+            .advance(&k1, dt / 6.0)
+            .advance(&k2, dt / 3.0)
+            .advance(&k3, dt / 3.0)
+            .advance(&k4, dt / 6.0)
+            .normalized()
+    }
+}
+
+/// velocity verlet (in its kick, drift, kick form)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Verlet;
+
+impl<S: State> Integrator<S> for Verlet {
+    fn integrate(&mut self, state: &S, t: secs, dt: secs, rate: &mut Rate<S>) -> S {
+        let half = dt / 2.0;
+        let k1 = rate(t, half, state);
+        let moved = state.advance_velocity(&k1, half).drift(dt);
+        let k2 = rate(t + half, half, &moved);
+        moved.advance_velocity(&k2, half).normalized()
+    }
+}
+
+// dormand prince coefficients
+const DP_C: [f64; 7] = [0.0, 1.0 / 5.0, 3.0 / 10.0, 4.0 / 5.0, 8.0 / 9.0, 1.0, 1.0];
+const DP_A: [&[f64]; 7] = [
+    &[],
+    &[1.0 / 5.0],
+    &[3.0 / 40.0, 9.0 / 40.0],
+    &[44.0 / 45.0, -56.0 / 15.0, 32.0 / 9.0],
+    &[19372.0 / 6561.0, -25360.0 / 2187.0, 64448.0 / 6561.0, -212.0 / 729.0],
+    &[9017.0 / 3168.0, -355.0 / 33.0, 46732.0 / 5247.0, 49.0 / 176.0, -5103.0 / 18656.0],
+    &[35.0 / 384.0, 0.0, 500.0 / 1113.0, 125.0 / 192.0, -2187.0 / 6784.0, 11.0 / 84.0],
+];
+/// 5th order solution (the same as the last row of `DP_A`)
+const DP_B5: [f64; 7] = [35.0 / 384.0, 0.0, 500.0 / 1113.0, 125.0 / 192.0, -2187.0 / 6784.0, 11.0 / 84.0, 0.0];
+/// 4th order solution, used for the error estimate
+const DP_B4: [f64; 7] = [
+    5179.0 / 57600.0,
+    0.0,
+    7571.0 / 16695.0,
+    393.0 / 640.0,
+    -92097.0 / 339200.0,
+    187.0 / 2100.0,
+    1.0 / 40.0,
+];
+
+/// adaptive dormand prince runge kutta 4(5)
+///
+/// the step asked for is split into as many smaller steps as are needed to keep the error of each one under the tolerance.
+/// like [`RK4`], the rates are taken at an instant
+#[derive(Debug, Clone, Copy)]
+pub struct RK45 {
+    /// largest error allowed per step
+    pub tolerance: f64,
+    /// steps will not be made any smaller than this, even if the tolerance is not met
+    pub min_step: secs,
+    /// step size to try next, kept between calls
+    next_step: Option<secs>,
+}
+
+impl RK45 {
+    pub fn new(tolerance: f64) -> Self {
+        Self {
+            tolerance,
+            min_step: 1e-6,
+            next_step: None,
+        }
+    }
+
+    fn weighted<S: State>(state: &S, k: &[S], weights: &[f64], dt: secs) -> S {
+        k.iter()
+            .zip(weights)
+            .filter(|(_, w)| **w != 0.0)
+            .fold(state.clone(), |s, (k, w)| s.advance(k, w * dt))
+    }
+
+    /// one attempt at a step, returning the 5th order result and the error estimate
+    fn attempt<S: State>(state: &S, t: secs, dt: secs, rate: &mut Rate<S>) -> (S, f64) {
+        let mut k: Vec<S> = Vec::with_capacity(7);
+        for (c, a) in DP_C.iter().zip(DP_A) {
+            let stage = Self::weighted(state, &k, a, dt);
+            k.push(rate(t + c * dt, 0.0, &stage));
+        }
+        let fifth = Self::weighted(state, &k, &DP_B5, dt);
+        let fourth = Self::weighted(state, &k, &DP_B4, dt);
+        let error = fifth.error(&fourth);
+        (fifth, error)
+    }
+}
+
+impl<S: State> Integrator<S> for RK45 {
+    fn integrate(&mut self, state: &S, t: secs, dt: secs, rate: &mut Rate<S>) -> S {
+        let end = t + dt;
+        let mut t = t;
+        let mut state = state.clone();
+        let mut step = self.next_step.unwrap_or(dt).min(dt);
+
+        while end - t > 1e-12 {
+            step = step.min(end - t);
+            let (next, error) = Self::attempt(&state, t, step, rate);
+            // how much the step can change by, from the error it had
+            // a nan or infinite error can't be trusted either way, so it is treated like a step that failed badly
+            let scale = if !error.is_finite() {
+                0.2
+            } else if error > 0.0 {
+                (0.9 * (self.tolerance / error).powf(0.2)).clamp(0.2, 5.0)
+            } else {
+                5.0
+            };
+            if error <= self.tolerance || step <= self.min_step {
+                t += step;
+                state = next.normalized();
+                // dont remember the step if it was only cut short to land on the end
+                if end - t > 1e-12 || scale < 1.0 {
+                    self.next_step = Some(step * scale);
+                }
+            }
+            step = (step * scale).max(self.min_step);
+        }
+
+        state
+    }
+}
+
+/// which integrator to use for a simulation
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum IntegrationMethod {
+    #[default]
+    Euler,
+    RK4,
+    Verlet,
+    /// adaptive, with the given tolerance per step
+    RK45(f64),
+}
+
+impl IntegrationMethod {
+    pub fn build<S: State>(self) -> Box<dyn Integrator<S>> {
+        match self {
+            Self::Euler => Box::new(Euler),
+            Self::RK4 => Box::new(RK4),
+            Self::Verlet => Box::new(Verlet),
+            Self::RK45(tolerance) => Box::new(RK45::new(tolerance)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    /// a mass on a spring (x'' = -x)
+    #[derive(Debug, Clone, Copy)]
+    struct Spring {
+        x: f64,
+        v: f64,
+    }
+
+    impl State for Spring {
+        fn advance(&self, rate: &Self, dt: secs) -> Self {
+            Self { x: self.x + rate.x * dt, v: self.v + rate.v * dt }
+        }
+
+        fn advance_velocity(&self, rate: &Self, dt: secs) -> Self {
+            Self { x: self.x, v: self.v + rate.v * dt }
+        }
+
+        fn drift(&self, dt: secs) -> Self {
+            Self { x: self.x + self.v * dt, v: self.v }
+        }
+
+        fn error(&self, other: &Self) -> f64 {
+            (self.x - other.x).abs().max((self.v - other.v).abs())
+        }
+    }
+
+    /// integrates a full period of the spring, and returns how far away from the start it ended up
+    fn period_error(method: IntegrationMethod, dt: secs) -> f64 {
+        let mut integrator = method.build();
+        let mut state = Spring { x: 1.0, v: 0.0 };
+        let mut t = 0.0;
+        let steps = (std::f64::consts::TAU / dt).round() as usize;
+        let dt = std::f64::consts::TAU / steps as f64;
+        for _ in 0..steps {
+            state = integrator.integrate(&state, t, dt, &mut |_, _, s: &Spring| Spring { x: s.v, v: -s.x });
+            t += dt;
+        }
+        state.error(&Spring { x: 1.0, v: 0.0 })
+    }
+
+    #[test]
+    fn orders() {
+        let euler = period_error(IntegrationMethod::Euler, 0.01);
+        let verlet = period_error(IntegrationMethod::Verlet, 0.01);
+        let rk4 = period_error(IntegrationMethod::RK4, 0.01);
+        assert!(euler < 0.02, "{}", euler);
+        assert!(verlet < 1e-4, "{}", verlet);
+        assert!(rk4 < 1e-8, "{}", rk4);
+    }
+
+    #[test]
+    fn adaptive() {
+        // a single huge step still ends up accurate
+        let mut integrator = RK45::new(1e-9);
+        let state = Spring { x: 1.0, v: 0.0 };
+        let end = integrator.integrate(&state, 0.0, std::f64::consts::PI, &mut |_, _, s: &Spring| Spring { x: s.v, v: -s.x });
+        assert_approx_eq!(end.x, -1.0, 1e-6);
+        assert_approx_eq!(end.v, 0.0, 1e-6);
+        assert!(period_error(IntegrationMethod::RK45(1e-9), 0.1) < 1e-6);
+    }
+
+    #[test]
+    fn adaptive_nan() {
+        // the step can never meet the tolerance, so it shrinks down to the smallest step and carries on
+        let mut integrator = RK45::new(1e-9);
+        integrator.min_step = 1e-3;
+        let state = Spring { x: 1.0, v: 0.0 };
+        let end = integrator.integrate(&state, 0.0, 0.01, &mut |_, _, _: &Spring| Spring { x: f64::NAN, v: 0.0 });
+        assert!(end.x.is_nan());
+        assert_eq!(end.v, 0.0);
+    }
+}
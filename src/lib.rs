@@ -0,0 +1,18 @@
+pub mod math;
+pub mod motor;
+pub mod consts;
+pub mod rocket;
+pub mod controll;
+pub mod logger;
+pub mod physics;
+pub mod integrator;
+pub mod aero;
+pub mod atmosphere;
+pub mod gravity;
+pub mod rail;
+pub mod events;
+pub mod recovery;
+pub mod stage;
+pub mod dispersion;
+
+pub use consts::*;
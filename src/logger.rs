@@ -18,6 +18,22 @@ pub struct CSVRow {
     pub vel_y: f64,
     pub vel_z: f64,
     pub mass: f64,
+    pub quat_w: f64,
+    pub quat_x: f64,
+    pub quat_y: f64,
+    pub quat_z: f64,
+    /// degrees
+    pub roll: f64,
+    pub pitch: f64,
+    pub yaw: f64,
+    /// rad/s, body frame
+    pub ang_vel_x: f64,
+    pub ang_vel_y: f64,
+    pub ang_vel_z: f64,
+    /// newtons
+    pub drag: f64,
+    /// the events that happened during the last tick, seperated by spaces
+    pub event: String,
 }
 
 #[derive(Debug)]
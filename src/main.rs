@@ -1,16 +1,11 @@
-pub mod math;
-pub mod motor;
-pub mod consts;
-pub mod rocket;
-pub mod controll;
-pub mod logger;
-pub mod physics;
-
-pub use consts::*;
-use logger::Logger;
-use math::F64x3;
-use motor::Motor;
-use rocket::RocketFactory;
+use rocketengine::dispersion::MonteCarlo;
+use rocketengine::integrator::IntegrationMethod;
+use rocketengine::logger::Logger;
+use rocketengine::math::{F64x3, DEG_TO_RAD};
+use rocketengine::motor::MotorCatalog;
+use rocketengine::rail::LaunchRail;
+use rocketengine::recovery::RecoveryDevice;
+use rocketengine::rocket::RocketFactory;
 
 /*
 x and y are the two horizontal axis, and z is the vertical axis
@@ -30,28 +25,83 @@ https://www.youtube.com/watch?v=nwgd1CV__rs&t=128s
 
 // ajust acordingly
 const TICK_TIME: f64 = 0.1;
-const SECS_TO_TICKS: usize = 10;
-const SIM_STEPS: usize = 10 * SECS_TO_TICKS;
+/// the simulation stops here if the rocket has not landed yet
+const MAX_TIME: f64 = 120.0;
 
-fn main() {
-    let factory = RocketFactory::with_mass(800.0)
+fn rocket() -> RocketFactory {
+    let catalog = MotorCatalog::builtin();
+
+    RocketFactory::with_mass(800.0)
+        // rk4 and rk45 sample the thrust curve at each stage, which misses impulse at this step size. verlet averages
+        // it over the step like euler does
+        .integrator(IntegrationMethod::Verlet)
+        // 41mm body tube
+        .drag(0.00132, 0.45)
+        .launch_rail(LaunchRail::new(1.0).elevation(85.0 * DEG_TO_RAD).friction(0.1))
+        .add_recovery(RecoveryDevice::parachute("chute", 0.45, 1.5))
+        .wind(F64x3::new(2.0, 0.0, 0.0))
         .add_engine(
             "ascent".into(),
-            Motor::new(
-                motor::raw::E12::DATA.to_vec(),
-                motor::raw::E12::DRY_WEIGHT,
-            )
-        );
+            catalog.find("E12").expect("E12 is a builtin motor")
+        )
+}
 
-    let mut r = factory.at(F64x3::new(0.0, 0.0, 5.0));
+/// `dispersion [runs] [seed]` flies the rocket many times with everything varied a bit, to see where it could land
+fn dispersion(mut args: impl Iterator<Item = String>) {
+    let runs = args.next().map_or(100, |runs| runs.parse().expect("runs should be a number"));
+    let seed = args.next().map_or(0, |seed| seed.parse().expect("seed should be a number"));
+    let results = MonteCarlo::new(runs, seed)
+        .time_step(TICK_TIME / 10.0)
+        .max_time(MAX_TIME)
+        .run(&rocket(), &["ascent"]);
+    results.write_csv("out/dispersion.csv").unwrap();
+    print!("{}", results);
+}
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    if args.next().as_deref() == Some("dispersion") {
+        dispersion(args);
+        return;
+    }
+
+    let mut r = rocket().at(F64x3::zero());
 
     let mut logger = Logger::open_file("out/launch.csv".into()).unwrap();
+    // one log for each stage that separates
+    let mut booster_loggers = vec![];
 
     r.light_engine("ascent".into());
 
-    for t in 0..SIM_STEPS {
+    while !r.all_landed() && r.time() < MAX_TIME {
         r.tick(TICK_TIME);
-        let time = t as f64 * TICK_TIME;
-        r.log(time, &mut logger);
+        if !r.landed() {
+            r.log(&mut logger);
+        }
+        for event in r.poll_events() {
+            println!("{:>8.3}s {}", event.time, event.event);
+        }
+        for (i, booster) in r.boosters_mut().iter_mut().enumerate() {
+            if booster_loggers.len() <= i {
+                booster_loggers.push(Logger::open_file(format!("out/booster_{}.csv", i).into()).unwrap());
+            }
+            if !booster.landed() {
+                booster.log(&mut booster_loggers[i]);
+            }
+            for event in booster.poll_events() {
+                println!("{:>8.3}s booster {}: {}", event.time, i, event.event);
+            }
+        }
+    }
+
+    match r.rail_exit() {
+        Some(exit) => println!("left the rail at {:.3}s, going {:.2}m/s", exit.time, exit.speed()),
+        None => println!("never left the rail"),
+    }
+    let landing = r.position();
+    println!("landed {:.1}m east and {:.1}m north of the pad", landing.x, landing.y);
+    for (i, booster) in r.boosters().iter().enumerate() {
+        let landing = booster.position();
+        println!("booster {} landed {:.1}m east and {:.1}m north of the pad", i, landing.x, landing.y);
     }
 }
@@ -1,7 +1,7 @@
 #[allow(unused_imports)]
 use assert_approx_eq::assert_approx_eq;
 use std::fmt::{Display, Formatter};
-use std::ops::{Mul, MulAssign};
+use std::ops::{Add, Mul, MulAssign};
 
 #[allow(unused_imports)]
 use crate::math::{DEG_TO_RAD, RAD_TO_DEG};
@@ -60,6 +60,22 @@ impl MulAssign for Quaternion {
     }
 }
 
+impl Mul<f64> for Quaternion {
+    type Output = Self;
+
+    fn mul(self, other: f64) -> Self {
+        Quaternion::new(self.w * other, self.x * other, self.y * other, self.z * other)
+    }
+}
+
+impl Add for Quaternion {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Quaternion::new(self.w + other.w, self.x + other.x, self.y + other.y, self.z + other.z)
+    }
+}
+
 impl From<F64x3> for Quaternion {
     fn from(v: F64x3) -> Quaternion {
         Quaternion::new(0.0, v.x, v.y, v.z)
@@ -71,6 +87,22 @@ impl Quaternion {
         Self { w, x, y, z }
     }
 
+    pub fn w(&self) -> f64 {
+        self.w
+    }
+
+    pub fn x(&self) -> f64 {
+        self.x
+    }
+
+    pub fn y(&self) -> f64 {
+        self.y
+    }
+
+    pub fn z(&self) -> f64 {
+        self.z
+    }
+
     /// Return the conjugate of the quaternion.
     pub fn conj(self) -> Quaternion {
         Quaternion::new(self.w, -self.x, -self.y, -self.z)
@@ -122,7 +154,7 @@ impl Quaternion {
 
     /// Return the quaternion from an axis and angle.
     pub fn from_axis_angle(axis: F64x3, angle: f64) -> Quaternion {
-        let sa = (angle / 2.0).cos();
+        let sa = (angle / 2.0).sin();
 
         let w = (angle / 2.0).cos();
         let x = axis.x * sa;
@@ -152,13 +184,12 @@ impl Quaternion {
 
     /// Convert a quaternion to euler angles.
     pub fn to_euler(self) -> F64x3 {
-        let x = 2.0
-            * (self.w * self.x + self.y * self.z)
-                .atan2(1.0 - 2.0 * (self.x.powi(2) + self.y.powi(2)));
-        let y = (2.0 * (self.w * self.y - self.z * self.x)).asin();
-        let z = 2.0
-            * (self.w * self.z + self.x * self.y)
-                .atan2(1.0 - 2.0 * (self.y.powi(2) + self.z.powi(2)));
+        let x = (2.0 * (self.w * self.x + self.y * self.z))
+            .atan2(1.0 - 2.0 * (self.x.powi(2) + self.y.powi(2)));
+        // clamped, as rounding can put it just outside of asin's range when pointing straight up or down
+        let y = (2.0 * (self.w * self.y - self.z * self.x)).clamp(-1.0, 1.0).asin();
+        let z = (2.0 * (self.w * self.z + self.x * self.y))
+            .atan2(1.0 - 2.0 * (self.y.powi(2) + self.z.powi(2)));
 
         F64x3 { x, y, z }
     }
@@ -234,11 +265,16 @@ fn test_quaternion_to_euler() {
     println!("{:?}", q);
     println!("{}", e * RAD_TO_DEG);
 
-    // assert_approx_eq!(e.x, et.x, 0.0001);
-    // assert_approx_eq!(e.y, et.y, 0.0001);
-    // assert_approx_eq!(e.z, et.z, 0.0001);
-    // FIXME low precision floats go brrr
-    assert_approx_eq!(e.x, et.x, 0.15);
-    assert_approx_eq!(e.y, et.y, 0.15);
-    assert_approx_eq!(e.z, et.z, 0.15);
+    assert_approx_eq!(e.x, et.x, 0.0001);
+    assert_approx_eq!(e.y, et.y, 0.0001);
+    assert_approx_eq!(e.z, et.z, 0.0001);
+}
+#[test]
+fn test_axis_angle() {
+    let q = Quaternion::from_axis_angle(F64x3::new(0.0, 0.0, 1.0), 90.0 * DEG_TO_RAD);
+    let v = q.rotate(F64x3::new(1.0, 0.0, 0.0));
+
+    assert_approx_eq!(v.x, 0.0, 0.0001);
+    assert_approx_eq!(v.y, 1.0, 0.0001);
+    assert_approx_eq!(v.z, 0.0, 0.0001);
 }
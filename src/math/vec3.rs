@@ -433,6 +433,8 @@ macro_rules! test_vec3_impl {
 }
 
 #[cfg(test)]
+// the test values are written for f64
+#[allow(clippy::excessive_precision)]
 mod vec3f32_tests {
     test_vec3_impl!(super::F32x3, f32);
 }
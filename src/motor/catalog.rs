@@ -0,0 +1,281 @@
+//! index of every known motor, shared with ZegeSim-rs
+
+use std::{fs, path::Path, sync::OnceLock};
+
+use super::{MotorData, MotorFileError};
+
+/// the motor files in `raw_thrust_profiles`, built into the binary
+const BUILTIN: &[(&str, &str)] = &[
+    ("Apogee_F10.rse", include_str!("../../raw_thrust_profiles/Apogee_F10.rse")),
+    ("Estes_D12.rse", include_str!("../../raw_thrust_profiles/Estes_D12.rse")),
+    ("Estes_E12.rse", include_str!("../../raw_thrust_profiles/Estes_E12.rse")),
+    ("Estes_F15.rse", include_str!("../../raw_thrust_profiles/Estes_F15.rse")),
+    ("Estes_C6.eng", include_str!("../../raw_thrust_profiles/Estes_C6.eng")),
+    ("AeroTech_E6.eng", include_str!("../../raw_thrust_profiles/AeroTech_E6.eng")),
+    ("AeroTech_E6-RCT.eng", include_str!("../../raw_thrust_profiles/AeroTech_E6-RCT.eng")),
+    ("AeroTech_G8ST.eng", include_str!("../../raw_thrust_profiles/AeroTech_G8ST.eng")),
+    ("AeroTech_G11.eng", include_str!("../../raw_thrust_profiles/AeroTech_G11.eng")),
+    ("AeroTech_G12-RCT.eng", include_str!("../../raw_thrust_profiles/AeroTech_G12-RCT.eng")),
+    ("AeroTech_G12ST.eng", include_str!("../../raw_thrust_profiles/AeroTech_G12ST.eng")),
+    ("AeroTech_H13ST.eng", include_str!("../../raw_thrust_profiles/AeroTech_H13ST.eng")),
+];
+
+/// builtin files ported from ZegeSim's old motor tables, which only had the thrust curves
+const SYNTHETIC: &[&str] = &[
+    "Estes_C6.eng",
+    "AeroTech_E6.eng",
+    "AeroTech_E6-RCT.eng",
+    "AeroTech_G8ST.eng",
+    "AeroTech_G11.eng",
+    "AeroTech_G12-RCT.eng",
+    "AeroTech_G12ST.eng",
+    "AeroTech_H13ST.eng",
+];
+
+/// impulse class letter for a total impulse in newton seconds (A is up to 2.5Ns, and every letter after doubles it)
+///
+/// anything smaller than an A is still classed as A
+pub fn impulse_class(total_impulse: f64) -> char {
+    let steps = (total_impulse / 2.5).log2().ceil().max(0.0) as u8;
+    (b'A' + steps.min(25)) as char
+}
+
+/// lowercase, without any separators, so `e6_rct` and `E6-RCT` are the same motor
+fn normalize(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+impl MotorData {
+    pub fn impulse_class(&self) -> char {
+        impulse_class(self.total_impulse())
+    }
+}
+
+/// a collection of motors that can be looked up by name, or searched
+#[derive(Debug, Clone, Default)]
+pub struct MotorCatalog {
+    motors: Vec<MotorData>,
+}
+
+impl MotorCatalog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// every motor in `raw_thrust_profiles`
+    pub fn builtin() -> &'static Self {
+        static CATALOG: OnceLock<MotorCatalog> = OnceLock::new();
+        CATALOG.get_or_init(|| {
+            let mut catalog = Self::new();
+            for (name, src) in BUILTIN {
+                let motors = if name.ends_with(".rse") {
+                    MotorData::parse_rse(src)
+                } else {
+                    MotorData::parse_eng(src)
+                };
+                let mut motors = motors.unwrap_or_else(|e| panic!("builtin motor file {} is invalid: {}", name, e));
+                for motor in &mut motors {
+                    motor.synthetic = SYNTHETIC.contains(name);
+                }
+                catalog.extend(motors);
+            }
+            catalog
+        })
+    }
+
+    /// loads every .rse and .eng file in a directory
+    pub fn load_dir(path: impl AsRef<Path>) -> Result<Self, MotorFileError> {
+        let mut catalog = Self::new();
+        let mut files = fs::read_dir(path)?
+            .map(|entry| entry.map(|e| e.path()))
+            .collect::<Result<Vec<_>, _>>()?;
+        // keep the order stable between platforms
+        files.sort();
+        for file in files {
+            catalog.add_file(file)?;
+        }
+        Ok(catalog)
+    }
+
+    /// loads the motors in a .rse or .eng file, other files are ignored
+    pub fn add_file(&mut self, path: impl AsRef<Path>) -> Result<(), MotorFileError> {
+        let path = path.as_ref();
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("rse") => self.extend(MotorData::from_rse_file(path)?),
+            Some("eng") => self.extend(MotorData::from_eng_file(path)?),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    pub fn add(&mut self, motor: MotorData) {
+        self.motors.push(motor);
+    }
+
+    pub fn extend(&mut self, motors: impl IntoIterator<Item = MotorData>) {
+        self.motors.extend(motors);
+    }
+
+    pub fn motors(&self) -> impl Iterator<Item = &MotorData> {
+        self.motors.iter()
+    }
+
+    /// finds a motor by designation (case and separators are ignored, so `e6_rct` finds `E6-RCT`),
+    /// optionally prefixed with the manufacturer (`Estes E12`)
+    ///
+    /// if there is no exact match, a designation that starts with the name is used if it is the only one (`h13` finds `H13ST`)
+    pub fn find(&self, name: &str) -> Option<&MotorData> {
+        let (manufacturer, designation) = match name.trim().rsplit_once(char::is_whitespace) {
+            Some((mfg, code)) => (Some(normalize(mfg)), normalize(code)),
+            None => (None, normalize(name)),
+        };
+        let candidates = || {
+            self.motors.iter().filter(|m| match &manufacturer {
+                Some(mfg) => normalize(&m.manufacturer).starts_with(mfg.as_str()),
+                None => true,
+            })
+        };
+
+        if let Some(exact) = candidates().find(|m| normalize(&m.designation) == designation) {
+            return Some(exact);
+        }
+        let mut prefixed = candidates().filter(|m| normalize(&m.designation).starts_with(&designation));
+        match (prefixed.next(), prefixed.next()) {
+            (Some(only), None) => Some(only),
+            _ => None,
+        }
+    }
+
+    /// starts a search of the catalog, eg `catalog.query().class('E').diameter(24.0).motors()`
+    pub fn query(&self) -> MotorQuery<'_> {
+        MotorQuery {
+            catalog: self,
+            manufacturer: None,
+            class: None,
+            diameter: None,
+            impulse: None,
+        }
+    }
+}
+
+/// a search of a [`MotorCatalog`], every filter that is set has to match
+#[derive(Debug, Clone)]
+pub struct MotorQuery<'a> {
+    catalog: &'a MotorCatalog,
+    manufacturer: Option<String>,
+    class: Option<char>,
+    /// mm
+    diameter: Option<f64>,
+    /// newton seconds, inclusive
+    impulse: Option<(f64, f64)>,
+}
+
+impl<'a> MotorQuery<'a> {
+    pub fn manufacturer(mut self, manufacturer: &str) -> Self {
+        self.manufacturer = Some(normalize(manufacturer));
+        self
+    }
+
+    /// impulse class letter
+    pub fn class(mut self, class: char) -> Self {
+        self.class = Some(class.to_ascii_uppercase());
+        self
+    }
+
+    /// diameter in mm (within half a mm, as files are not consistent about 29 vs 29.0 vs 28.7). synthetic motors never
+    /// match, as their diameter is a guess
+    pub fn diameter(mut self, diameter: f64) -> Self {
+        self.diameter = Some(diameter);
+        self
+    }
+
+    /// total impulse range in newton seconds
+    pub fn impulse(mut self, min: f64, max: f64) -> Self {
+        self.impulse = Some((min, max));
+        self
+    }
+
+    pub fn matches(&self, motor: &MotorData) -> bool {
+        let total_impulse = motor.total_impulse();
+        self.manufacturer
+            .as_ref()
+            .is_none_or(|mfg| normalize(&motor.manufacturer).starts_with(mfg.as_str()))
+            && self.class.is_none_or(|class| impulse_class(total_impulse) == class)
+            && self.diameter.is_none_or(|dia| !motor.synthetic && (motor.diameter - dia).abs() <= 0.5)
+            && self
+                .impulse
+                .is_none_or(|(min, max)| min <= total_impulse && total_impulse <= max)
+    }
+
+    pub fn motors(self) -> impl Iterator<Item = &'a MotorData> {
+        self.catalog.motors.iter().filter(move |m| self.matches(m))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::motor::Motor;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn classes() {
+        assert_eq!(impulse_class(1.0), 'A');
+        assert_eq!(impulse_class(2.5), 'A');
+        assert_eq!(impulse_class(2.6), 'B');
+        assert_eq!(impulse_class(49.6), 'F');
+        assert_eq!(impulse_class(377.4), 'I');
+    }
+
+    #[test]
+    fn builtin_lookup() {
+        let catalog = MotorCatalog::builtin();
+        assert_eq!(catalog.find("E12").unwrap().manufacturer, "Estes Industries, Inc.");
+        assert_eq!(catalog.find("estes e12").unwrap().designation, "E12");
+        assert_eq!(catalog.find("e6_rct").unwrap().designation, "E6-RCT");
+        assert_eq!(catalog.find("e6").unwrap().designation, "E6");
+        assert_eq!(catalog.find("h13").unwrap().designation, "H13ST");
+        // G12-RCT and G12ST
+        assert!(catalog.find("g12").is_none());
+        assert!(catalog.find("aerotech e12").is_none());
+    }
+
+    #[test]
+    fn builtin_query() {
+        let catalog = MotorCatalog::builtin();
+        let e24: Vec<&str> = catalog
+            .query()
+            .class('E')
+            .diameter(24.0)
+            .motors()
+            .map(|m| m.designation.as_str())
+            .collect();
+        // the aerotech E6s are 24mm too, but their sizes are not known
+        assert_eq!(e24, vec!["E12"]);
+        assert!(catalog.find("e6").unwrap().synthetic);
+        assert!(!catalog.find("e12").unwrap().synthetic);
+        assert_eq!(catalog.query().manufacturer("aerotech").class('G').motors().count(), 4);
+        assert_eq!(catalog.query().impulse(40.0, 80.0).motors().count(), 2);
+    }
+
+    #[test]
+    fn synthetic_weights() {
+        let c6 = MotorCatalog::builtin().find("c6").unwrap();
+        assert_eq!(c6.total_weight, 0.0);
+        assert_eq!(c6.dry_weight(), 0.0);
+        // flown once it is told what it weighs
+        let mut motor = Motor::from(&c6.clone().with_weights(24.3, 10.8));
+        assert_approx_eq!(motor.weight_at(0.0), 0.0243);
+        motor.light(0.0);
+        assert_approx_eq!(motor.weight_at(100.0), 0.0135);
+    }
+
+    #[test]
+    #[should_panic]
+    fn synthetic_without_weights() {
+        let _ = Motor::from(MotorCatalog::builtin().find("c6").unwrap());
+    }
+}
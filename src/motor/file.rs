@@ -0,0 +1,359 @@
+//! runtime loading of motor thrust curves, from RockSim (`.rse`) and RASP (`.eng`) files
+//!
+//! see <https://www.thrustcurve.org/info/raspformat.html> for the .eng format
+
+use std::{fmt, fs, io, path::Path};
+
+use xml::{attribute::OwnedAttribute, reader::XmlEvent, EventReader};
+
+use super::Motor;
+use crate::{secs, KG_TO_GRAMS};
+
+/// everything that is known about a motor from its data file
+#[derive(Debug, Clone, PartialEq)]
+pub struct MotorData {
+    pub manufacturer: String,
+    /// the motor code, eg `F15`
+    pub designation: String,
+    /// mm
+    pub diameter: f64,
+    /// mm
+    pub length: f64,
+    /// ejection delays that the motor is sold with. empty for plugged motors
+    pub delays: Vec<secs>,
+    /// weight of the whole motor before it is lit, in grams
+    pub total_weight: f64,
+    /// grams
+    pub propellant_weight: f64,
+    /// time (secs), thrust (newtons), and propellant left (grams)
+    pub data: Vec<[f64; 3]>,
+    /// only the thrust curve is known, the dimensions and weights are 0. these are left out of catalog searches by size,
+    /// and need [`with_weights`](Self::with_weights) before they can be flown
+    pub synthetic: bool,
+}
+
+#[derive(Debug)]
+pub enum MotorFileError {
+    Io(io::Error),
+    Xml(xml::reader::Error),
+    /// a required attribute or header field was not there
+    Missing { field: &'static str },
+    /// a number that did not parse
+    InvalidNumber { field: &'static str, value: String },
+    /// the file parsed, but did not contain any motors
+    NoMotors,
+}
+
+impl fmt::Display for MotorFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "failed to read motor file: {}", e),
+            Self::Xml(e) => write!(f, "malformed rse file: {}", e),
+            Self::Missing { field } => write!(f, "motor file is missing `{}`", field),
+            Self::InvalidNumber { field, value } => {
+                write!(f, "invalid number {:?} for `{}` in motor file", value, field)
+            }
+            Self::NoMotors => write!(f, "motor file does not contain any motors"),
+        }
+    }
+}
+
+impl std::error::Error for MotorFileError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            Self::Xml(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for MotorFileError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<xml::reader::Error> for MotorFileError {
+    fn from(e: xml::reader::Error) -> Self {
+        Self::Xml(e)
+    }
+}
+
+fn parse_number(field: &'static str, value: &str) -> Result<f64, MotorFileError> {
+    value.trim().parse().map_err(|_| MotorFileError::InvalidNumber {
+        field,
+        value: value.to_string(),
+    })
+}
+
+/// parses a delay list, like `0,4,6,8` (rse) or `3-5-7` (eng). `P` (plugged) entries are skipped
+fn parse_delays(field: &'static str, raw: &str, separator: char) -> Result<Vec<secs>, MotorFileError> {
+    raw.split(separator)
+        .map(str::trim)
+        .filter(|d| !d.is_empty() && !d.eq_ignore_ascii_case("p"))
+        .map(|d| parse_number(field, d))
+        .collect()
+}
+
+impl MotorData {
+    /// a motor with one of the ejection delays it is sold with, None if it does not come with that delay
+    pub fn with_delay(&self, delay: secs) -> Option<Motor> {
+        self.delays
+            .contains(&delay)
+            .then(|| Motor::from_data(self).with_ejection_delay(delay))
+    }
+
+    /// the motor with its weights (grams) filled in, so a synthetic motor can be flown
+    pub fn with_weights(mut self, total_weight: f64, propellant_weight: f64) -> Self {
+        self.total_weight = total_weight;
+        self.propellant_weight = propellant_weight;
+        self.fill_mass_from_impulse();
+        self.synthetic = false;
+        self
+    }
+
+    /// loads every motor in a RockSim .rse file
+    pub fn from_rse_file(path: impl AsRef<Path>) -> Result<Vec<Self>, MotorFileError> {
+        Self::parse_rse(&fs::read_to_string(path)?)
+    }
+
+    /// loads every motor in a RASP .eng file
+    pub fn from_eng_file(path: impl AsRef<Path>) -> Result<Vec<Self>, MotorFileError> {
+        Self::parse_eng(&fs::read_to_string(path)?)
+    }
+
+    /// parses the contents of a .rse file
+    pub fn parse_rse(src: &str) -> Result<Vec<Self>, MotorFileError> {
+        fn attr<'a>(attrs: &'a [OwnedAttribute], name: &'static str) -> Option<&'a str> {
+            attrs
+                .iter()
+                .find(|a| a.name.local_name == name)
+                .map(|a| a.value.as_str())
+        }
+
+        fn required<'a>(attrs: &'a [OwnedAttribute], name: &'static str) -> Result<&'a str, MotorFileError> {
+            attr(attrs, name).ok_or(MotorFileError::Missing { field: name })
+        }
+
+        let mut motors = vec![];
+        let mut current: Option<MotorData> = None;
+        // whether the current motor has a mass column, if not it is calculated from the impulse
+        let mut has_mass = true;
+
+        for event in EventReader::new(src.as_bytes()) {
+            match event? {
+                XmlEvent::StartElement { name, attributes, .. } if name.local_name == "engine" => {
+                    let total_weight = parse_number("initWt", required(&attributes, "initWt")?)?;
+                    let propellant_weight = parse_number("propWt", required(&attributes, "propWt")?)?;
+                    current = Some(MotorData {
+                        manufacturer: attr(&attributes, "mfg").unwrap_or_default().to_string(),
+                        designation: required(&attributes, "code")?.to_string(),
+                        diameter: parse_number("dia", required(&attributes, "dia")?)?,
+                        length: parse_number("len", required(&attributes, "len")?)?,
+                        delays: parse_delays("delays", attr(&attributes, "delays").unwrap_or_default(), ',')?,
+                        total_weight,
+                        propellant_weight,
+                        data: vec![],
+                        synthetic: false,
+                    });
+                    has_mass = true;
+                }
+                XmlEvent::StartElement { name, attributes, .. } if name.local_name == "eng-data" => {
+                    let motor = current.as_mut().ok_or(MotorFileError::Missing { field: "engine" })?;
+                    let t = parse_number("t", required(&attributes, "t")?)?;
+                    let f = parse_number("f", required(&attributes, "f")?)?;
+                    let m = match attr(&attributes, "m") {
+                        // some files have `-0.` at burnout
+                        Some(m) => parse_number("m", m)?.max(0.0),
+                        None => {
+                            has_mass = false;
+                            0.0
+                        }
+                    };
+                    motor.data.push([t, f, m]);
+                }
+                XmlEvent::EndElement { name } if name.local_name == "engine" => {
+                    if let Some(mut motor) = current.take() {
+                        if motor.data.is_empty() {
+                            return Err(MotorFileError::Missing { field: "eng-data" });
+                        }
+                        if !has_mass {
+                            motor.fill_mass_from_impulse();
+                        }
+                        motors.push(motor);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        if motors.is_empty() {
+            Err(MotorFileError::NoMotors)
+        } else {
+            Ok(motors)
+        }
+    }
+
+    /// parses the contents of a .eng file
+    ///
+    /// .eng files do not have a mass column, so the propellant mass is taken to burn proportionally to the impulse delivered
+    pub fn parse_eng(src: &str) -> Result<Vec<Self>, MotorFileError> {
+        let mut motors = vec![];
+        let mut current: Option<MotorData> = None;
+
+        // anything after a `;` is a comment
+        let lines = src
+            .lines()
+            .map(|l| l.split(';').next().unwrap_or_default().trim())
+            .filter(|l| !l.is_empty());
+
+        for line in lines {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let is_data = fields.len() == 2 && fields.iter().all(|f| f.parse::<f64>().is_ok());
+
+            if is_data {
+                let motor = current.as_mut().ok_or(MotorFileError::Missing { field: "header" })?;
+                let t = parse_number("time", fields[0])?;
+                let f = parse_number("thrust", fields[1])?;
+                // most .eng files leave out the starting point
+                if motor.data.is_empty() && t > 0.0 {
+                    motor.data.push([0.0, 0.0, 0.0]);
+                }
+                motor.data.push([t, f, 0.0]);
+            } else {
+                if let Some(motor) = current.take() {
+                    motors.push(motor);
+                }
+                // name diameter length delays propellant_weight total_weight manufacturer
+                if fields.len() < 7 {
+                    return Err(MotorFileError::Missing { field: "manufacturer" });
+                }
+                current = Some(MotorData {
+                    manufacturer: fields[6..].join(" "),
+                    designation: fields[0].to_string(),
+                    diameter: parse_number("diameter", fields[1])?,
+                    length: parse_number("length", fields[2])?,
+                    delays: parse_delays("delays", fields[3], '-')?,
+                    propellant_weight: parse_number("propellant weight", fields[4])? * KG_TO_GRAMS,
+                    total_weight: parse_number("total weight", fields[5])? * KG_TO_GRAMS,
+                    data: vec![],
+                    synthetic: false,
+                });
+            }
+        }
+        if let Some(motor) = current.take() {
+            motors.push(motor);
+        }
+
+        for motor in &mut motors {
+            if motor.data.is_empty() {
+                return Err(MotorFileError::Missing { field: "thrust data" });
+            }
+            motor.fill_mass_from_impulse();
+        }
+
+        if motors.is_empty() {
+            Err(MotorFileError::NoMotors)
+        } else {
+            Ok(motors)
+        }
+    }
+
+    /// weight of the motor casing (and everything else that is not propellant), in grams
+    pub fn dry_weight(&self) -> f64 {
+        self.total_weight - self.propellant_weight
+    }
+
+    /// total impulse of the thrust curve, in newton seconds
+    pub fn total_impulse(&self) -> f64 {
+        self.data
+            .windows(2)
+            .map(|w| (w[1][0] - w[0][0]) * (w[0][1] + w[1][1]) / 2.0)
+            .sum()
+    }
+
+    /// sets the propellant mass column, burning the propellant proportionally to the impulse delivered
+    fn fill_mass_from_impulse(&mut self) {
+        let total = self.total_impulse();
+        let mut delivered = 0.0;
+        let mut last: Option<[f64; 3]> = None;
+        for point in &mut self.data {
+            if let Some(last) = last {
+                delivered += (point[0] - last[0]) * (point[1] + last[1]) / 2.0;
+            }
+            point[2] = if total > 0.0 {
+                self.propellant_weight * (1.0 - delivered / total).max(0.0)
+            } else {
+                self.propellant_weight
+            };
+            last = Some(*point);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn parses_rse() {
+        let motors = MotorData::parse_rse(include_str!("../../raw_thrust_profiles/Estes_F15.rse")).unwrap();
+        assert_eq!(motors.len(), 1);
+        let f15 = &motors[0];
+        assert_eq!(f15.designation, "F15");
+        assert_eq!(f15.manufacturer, "Estes Industries, Inc.");
+        assert_eq!(f15.delays, vec![0.0, 4.0, 6.0, 8.0]);
+        assert_eq!(f15.delays, crate::motor::raw::F15::DELAYS.to_vec());
+        assert_approx_eq!(f15.dry_weight(), 43.0);
+        assert_eq!(f15.data, crate::motor::raw::F15::DATA.to_vec());
+        assert_approx_eq!(f15.total_impulse(), 49.609, 0.1);
+
+        assert!(f15.with_delay(5.0).is_none());
+        let mut motor = f15.with_delay(6.0).unwrap();
+        assert_eq!(motor.ejection_time(), None);
+        motor.light(1.0);
+        assert_approx_eq!(motor.ejection_time().unwrap(), 1.0 + motor.burn_time() + 6.0);
+    }
+
+    #[test]
+    fn parses_eng() {
+        let src = "
+; a comment
+C6 18 70 0-3-5-7 0.0108 0.0243 Estes
+   0.1 10.0
+   0.2 5.0 ; trailing comment
+   0.3 0.0
+;
+";
+        let motors = MotorData::parse_eng(src).unwrap();
+        assert_eq!(motors.len(), 1);
+        let c6 = &motors[0];
+        assert_eq!(c6.designation, "C6");
+        assert_eq!(c6.manufacturer, "Estes");
+        assert_eq!(c6.delays, vec![0.0, 3.0, 5.0, 7.0]);
+        assert_approx_eq!(c6.propellant_weight, 10.8);
+        assert_eq!(c6.data.len(), 4);
+        assert_eq!(c6.data[0], [0.0, 0.0, 10.8]);
+        assert_approx_eq!(c6.total_impulse(), 1.5);
+        // half of the impulse is delivered by 0.1 secs
+        assert_approx_eq!(c6.data[1][2], 10.8 * (1.0 - 0.5 / 1.5));
+        assert_approx_eq!(c6.data[3][2], 0.0);
+    }
+
+    #[test]
+    fn rejects_malformed() {
+        assert!(matches!(MotorData::parse_eng("C6 18 70 0 0.01"), Err(MotorFileError::Missing { .. })));
+        assert!(matches!(
+            MotorData::parse_eng("C6 18 70 0 abc 0.02 Estes\n0.1 1.0"),
+            Err(MotorFileError::InvalidNumber { .. })
+        ));
+        assert!(matches!(MotorData::parse_eng(""), Err(MotorFileError::NoMotors)));
+        assert!(matches!(MotorData::parse_rse("<engine-database"), Err(MotorFileError::Xml(_))));
+        assert!(matches!(
+            MotorData::parse_rse("<engine code=\"F15\"><data><eng-data t=\"0\" f=\"0\"/></data></engine>"),
+            Err(MotorFileError::Missing { field: "initWt" })
+        ));
+    }
+}
@@ -1,108 +1,346 @@
 pub mod raw;
+pub mod file;
+pub mod catalog;
+pub mod mount;
 
-use crate::{secs, math::F64x3, GRAMS_TO_KG};
+use std::path::Path;
 
-fn interpolate_two(current: (f64, F64x3, f64), next: (f64, F64x3, f64), interpolated_step: f64) -> Vec<(f64, F64x3, f64)> {
-    //TODO check if this creates two many intermediate steps
-    let dt = next.0 - current.0;
-    let num_steps = (dt/interpolated_step).ceil();
-    let change_thrust = next.1 - current.1;
-    let change_thrust_per_step = change_thrust / num_steps;
-    let change_wt = next.2 - current.2;
-    let change_wt_per_step = change_wt / num_steps;
+use crate::{secs, GRAMS_TO_KG};
 
-    let mut res = vec![];
+pub use file::{MotorData, MotorFileError};
+pub use catalog::{MotorCatalog, MotorQuery};
+pub use mount::Mount;
 
-    for i in 0..=num_steps as i64 {
-        res.push((interpolated_step, current.1 + change_thrust_per_step * i as f64, current.2 + change_wt_per_step * i as f64))
-    }
-
-    res
-}
-
-pub fn interpolate_thrust(original: Vec<(secs, F64x3, f64)>, interpolated_step: secs) -> Vec<(secs, F64x3, f64)> {
-    let mut original_i = original.into_iter();
-    let mut last = match original_i.next() {
-        Some(v) => {v},
-        None => {return vec![]},
-    };
-
-    let mut res = vec![];
-
-    for next in original_i {
-        res.append(&mut interpolate_two(last, next, interpolated_step));
-        last = next;
-    }
-
-    res
+/// how the propellant mass of a motor goes down as it burns
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PropellantModel {
+    /// interpolate the propellant column of the thrust curve (the `m` column of .rse files)
+    #[default]
+    Curve,
+    /// the propellant burns proportionally to the impulse delivered
+    Impulse,
 }
 
+/// a rocket motor, and its thrust curve
+///
+/// the curve is never consumed, everything is looked up by the time since ignition, so a motor can be
+/// queried at any time (or reset and lit again)
 #[derive(Clone, Debug)]
 pub struct Motor {
-    /// time left for each step of forces, and the force for that period of time (interpolated)
-    /// 
-    /// UNIT IS GRAMS AEEEEEEEE
-    forces: Vec<(secs, F64x3, f64)>,
-    lit: bool,
+    /// time since ignition (secs), thrust (newtons), and propellant left (grams)
+    curve: Vec<[f64; 3]>,
+    /// impulse delivered by the start of each point in `curve`, in newton seconds
+    impulse: Vec<f64>,
+    /// simulation time that the motor was lit at
+    ignition_time: Option<secs>,
+    /// grams
     dry_weight: f64,
+    propellant_model: PropellantModel,
+    /// seconds after burnout that the ejection charge fires, None for a plugged motor
+    ejection_delay: Option<secs>,
+    /// newton seconds the ejection charge gives the rocket, along its body (forwards is positive)
+    ejection_impulse: f64,
+}
+
+impl From<&MotorData> for Motor {
+    fn from(data: &MotorData) -> Self {
+        Self::from_data(data)
+    }
 }
 
 impl Motor {
+    /// creates a motor from a thrust curve of time since ignition (secs), thrust (newtons) and propellant left (grams), and the dry weight in grams
     pub fn new(raw: Vec<[f64; 3]>, dry_weight: f64) -> Self {
-        let forces = raw.into_iter().map(|i| {(i[0], F64x3::new(0.0, 0.0, i[1]), i[2])}).collect();
+        let mut impulse = Vec::with_capacity(raw.len());
+        let mut total = 0.0;
+        for (i, point) in raw.iter().enumerate() {
+            if i > 0 {
+                let last = raw[i - 1];
+                total += (point[0] - last[0]) * (point[1] + last[1]) / 2.0;
+            }
+            impulse.push(total);
+        }
         Self {
-            forces,
+            curve: raw,
+            impulse,
+            ignition_time: None,
             dry_weight,
-            lit: false,
+            propellant_model: PropellantModel::default(),
+            ejection_delay: None,
+            ejection_impulse: 0.0,
         }
     }
 
-    pub fn thrust_for(&mut self, dt: secs) -> Option<F64x3> {
-        if self.forces.is_empty() {
-            None
-        } else if !self.lit {
-            Some(F64x3::zero())
-        } else {
-            let mut latest_force = vec![self.forces[0].1];
-            let mut change = dt;
-            loop {
-                if self.forces.is_empty() {
-                    break;
-                }
-                self.forces[0].0 -= change;
-                if self.forces[0].0 <= 0.0 {
-                    change = self.forces[0].0;
-                    latest_force.push(self.forces.remove(0).1);
+    pub fn with_propellant_model(mut self, model: PropellantModel) -> Self {
+        self.propellant_model = model;
+        self
+    }
+
+    /// multiplies the thrust (and so the total impulse) of the motor by `scale`, keeping the burn time the same
+    ///
+    /// real motors vary a few percent from their published curve
+    pub fn with_impulse_scale(mut self, scale: f64) -> Self {
+        self.curve.iter_mut().for_each(|point| point[1] *= scale);
+        self.impulse.iter_mut().for_each(|impulse| *impulse *= scale);
+        self
+    }
+
+    /// fires an ejection charge this long after burnout
+    pub fn with_ejection_delay(mut self, delay: secs) -> Self {
+        self.ejection_delay = Some(delay);
+        self
+    }
+
+    /// the kick (newton seconds, along the body) the ejection charge gives the rocket. negative pushes it backwards
+    pub fn with_ejection_impulse(mut self, impulse: f64) -> Self {
+        self.ejection_impulse = impulse;
+        self
+    }
+
+    pub fn ejection_delay(&self) -> Option<secs> {
+        self.ejection_delay
+    }
+
+    pub fn ejection_impulse(&self) -> f64 {
+        self.ejection_impulse
+    }
+
+    /// the simulation time that the ejection charge fires at, if the motor has one and has been lit
+    pub fn ejection_time(&self) -> Option<secs> {
+        Some(self.ignition_time? + self.burn_time() + self.ejection_delay?)
+    }
+
+    /// creates a motor from loaded motor data
+    ///
+    /// panics if the motor is synthetic, it has no weights to fly with until they are given with
+    /// [`MotorData::with_weights`]
+    pub fn from_data(data: &MotorData) -> Self {
+        assert!(
+            !data.synthetic,
+            "{} {} only has a thrust curve, give it its weights with MotorData::with_weights",
+            data.manufacturer, data.designation
+        );
+        Self::new(data.data.clone(), data.dry_weight())
+    }
+
+    /// loads the first motor in a RockSim .rse file
+    pub fn from_rse_file(path: impl AsRef<Path>) -> Result<Self, MotorFileError> {
+        Ok(Self::from_data(&MotorData::from_rse_file(path)?[0]))
+    }
+
+    /// loads the first motor in a RASP .eng file
+    pub fn from_eng_file(path: impl AsRef<Path>) -> Result<Self, MotorFileError> {
+        Ok(Self::from_data(&MotorData::from_eng_file(path)?[0]))
+    }
+
+    /// index of the curve segment that `t` (time since ignition) is in, the segment is between this point and the next
+    fn segment(&self, t: secs) -> usize {
+        self.curve.partition_point(|p| p[0] <= t).saturating_sub(1)
+    }
+
+    /// thrust in newtons, `t` seconds after ignition
+    pub fn thrust_at(&self, t: secs) -> f64 {
+        if self.curve.is_empty() || t < self.curve[0][0] || t > self.burn_time() {
+            return 0.0;
+        }
+        let i = self.segment(t);
+        match self.curve.get(i + 1) {
+            Some(next) => {
+                let current = self.curve[i];
+                let span = next[0] - current[0];
+                if span <= 0.0 {
+                    next[1]
                 } else {
-                    break;
+                    current[1] + (next[1] - current[1]) * (t - current[0]) / span
                 }
             }
-            let mut sum = F64x3::zero();
-            let len = latest_force.len();
-            for i in latest_force {
-                sum += i;
-            }
-            Some(sum/len as f64)
+            None => self.curve[i][1],
+        }
+    }
+
+    /// impulse delivered from ignition until `t` seconds after ignition, in newton seconds
+    fn impulse_until(&self, t: secs) -> f64 {
+        if self.curve.is_empty() || t <= self.curve[0][0] {
+            return 0.0;
+        }
+        if t >= self.burn_time() {
+            return self.total_impulse();
         }
+        let i = self.segment(t);
+        let start = self.curve[i];
+        // the thrust is linear over the segment, so this is the exact area under it
+        self.impulse[i] + (t - start[0]) * (start[1] + self.thrust_at(t)) / 2.0
     }
 
-    pub fn burnt_out(&self) -> bool {
-        self.forces.is_empty()
+    /// impulse delivered between `t0` and `t1` seconds after ignition, in newton seconds
+    ///
+    /// this is the exact integral of the thrust curve, so summing it over steps of any size gives the total impulse of the motor
+    pub fn impulse_between(&self, t0: secs, t1: secs) -> f64 {
+        self.impulse_until(t1) - self.impulse_until(t0)
+    }
+
+    /// total impulse of the motor, in newton seconds
+    pub fn total_impulse(&self) -> f64 {
+        self.impulse.last().copied().unwrap_or(0.0)
+    }
+
+    /// time from ignition until the end of the thrust curve
+    pub fn burn_time(&self) -> secs {
+        self.curve.last().map(|p| p[0]).unwrap_or(0.0)
+    }
+
+    /// the simulation time that the motor was lit at
+    pub fn ignition_time(&self) -> Option<secs> {
+        self.ignition_time
+    }
+
+    /// time since ignition at the simulation time `time`, if the motor has been lit
+    pub fn time_since_ignition(&self, time: secs) -> Option<secs> {
+        self.ignition_time.map(|ignition| time - ignition)
+    }
+
+    /// average thrust (newtons) between the simulation times `t0` and `t1`, which delivers exactly the impulse of the curve over that time
+    pub fn average_thrust(&self, t0: secs, t1: secs) -> f64 {
+        match self.ignition_time {
+            Some(ignition) if t1 > t0 => self.impulse_between(t0 - ignition, t1 - ignition) / (t1 - t0),
+            _ => 0.0,
+        }
+    }
+
+    pub fn burnt_out(&self, time: secs) -> bool {
+        self.time_since_ignition(time).is_some_and(|t| t >= self.burn_time())
     }
 
     pub fn lit(&self) -> bool {
-        self.lit
+        self.ignition_time.is_some()
+    }
+
+    /// lights the motor at the simulation time `time`
+    pub fn light(&mut self, time: secs) {
+        self.ignition_time = Some(time);
+    }
+
+    /// puts the motor back to how it was before it was lit
+    pub fn reset(&mut self) {
+        self.ignition_time = None;
     }
 
-    pub fn light(&mut self) {
-        self.lit = true;
+    /// propellant left (grams), `t` seconds after ignition
+    pub fn propellant_at(&self, t: secs) -> f64 {
+        let (first, last) = match (self.curve.first(), self.curve.last()) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return 0.0,
+        };
+        if t <= first[0] {
+            return first[2];
+        }
+        match self.propellant_model {
+            PropellantModel::Curve => {
+                if t >= last[0] {
+                    return last[2];
+                }
+                let i = self.segment(t);
+                let current = self.curve[i];
+                let next = self.curve[i + 1];
+                let span = next[0] - current[0];
+                if span <= 0.0 {
+                    next[2]
+                } else {
+                    current[2] + (next[2] - current[2]) * (t - current[0]) / span
+                }
+            }
+            PropellantModel::Impulse => {
+                let total = self.total_impulse();
+                if total <= 0.0 {
+                    first[2]
+                } else {
+                    first[2] * (1.0 - self.impulse_until(t) / total)
+                }
+            }
+        }
     }
 
-    pub fn weight(&self) -> f64 {
-        (self.forces.get(0).unwrap_or(&(0.0, F64x3::zero(), 0.0)).2 + self.dry_weight) * GRAMS_TO_KG
+    /// weight in kg at the simulation time `time`, the full weight until the motor is lit, and the dry weight after burnout
+    pub fn weight_at(&self, time: secs) -> f64 {
+        let propellant = match self.time_since_ignition(time) {
+            Some(t) => self.propellant_at(t),
+            None => self.propellant_at(f64::NEG_INFINITY),
+        };
+        (propellant + self.dry_weight) * GRAMS_TO_KG
     }
 
+    /// # Safety
+    ///
+    /// dont
     pub const unsafe fn explode(self) -> ! {
         std::hint::unreachable_unchecked();
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    fn f15() -> Motor {
+        MotorCatalog::builtin().find("F15").unwrap().into()
+    }
+
+    #[test]
+    fn thrust_lookup() {
+        let motor = f15();
+        assert_eq!(motor.thrust_at(-1.0), 0.0);
+        assert_eq!(motor.thrust_at(0.0), 0.0);
+        assert_approx_eq!(motor.thrust_at(0.419), 25.26);
+        // halfway between 0.419 and 0.477
+        assert_approx_eq!(motor.thrust_at(0.448), (25.26 + 23.074) / 2.0);
+        assert_eq!(motor.thrust_at(10.0), 0.0);
+        // looking things up does not use the curve up
+        assert_approx_eq!(motor.thrust_at(0.419), 25.26);
+    }
+
+    #[test]
+    fn impulse_is_conserved() {
+        let motor = f15();
+        assert_approx_eq!(motor.total_impulse(), 49.609, 0.1);
+        for dt in [0.001, 0.01, 0.1, 0.3, 1.0, 2.5] {
+            let mut t = -dt / 2.0;
+            let mut total = 0.0;
+            while t < 5.0 {
+                total += motor.impulse_between(t, t + dt);
+                t += dt;
+            }
+            assert_approx_eq!(total, motor.total_impulse(), 1e-9);
+        }
+    }
+
+    #[test]
+    fn average_thrust() {
+        let mut motor = f15();
+        assert_eq!(motor.average_thrust(0.0, 1.0), 0.0);
+        motor.light(2.0);
+        assert_approx_eq!(motor.average_thrust(2.0, 12.0) * 10.0, motor.total_impulse());
+        assert!(motor.burnt_out(5.45));
+        motor.reset();
+        assert!(!motor.lit());
+    }
+
+    #[test]
+    fn propellant_depletion() {
+        let mut motor = f15();
+        motor.light(1.0);
+        assert_approx_eq!(motor.weight_at(0.0), 0.103);
+        assert_approx_eq!(motor.weight_at(1.0), 0.103);
+        // halfway between the 0.419 and 0.477 points
+        assert_approx_eq!(motor.propellant_at(0.448), (54.0771 + 52.3818) / 2.0);
+        assert_approx_eq!(motor.weight_at(100.0), 0.043);
+
+        let motor = motor.with_propellant_model(PropellantModel::Impulse);
+        let half = motor.propellant_at(0.0) / 2.0;
+        let mut t = 0.0;
+        while motor.impulse_between(0.0, t) < motor.total_impulse() / 2.0 {
+            t += 0.0001;
+        }
+        assert_approx_eq!(motor.propellant_at(t), half, 0.01);
+        assert_approx_eq!(motor.weight_at(100.0), 0.043);
+    }
+}
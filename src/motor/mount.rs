@@ -0,0 +1,83 @@
+//! where a motor sits in the rocket, and which way it pushes
+
+use crate::math::{F64x3, Quaternion};
+
+/// position and thrust direction of a motor, in the body frame of the rocket
+///
+/// a motor that is off the center line (like the outer motors of a cluster) or pointing off the rocket's axis
+/// pushes on the rocket with a torque as well as a force
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Mount {
+    /// where the thrust acts, m from the center of mass
+    pub position: F64x3,
+    /// unit vector that the thrust pushes along
+    pub direction: F64x3,
+}
+
+impl Default for Mount {
+    /// on the center of mass, pushing straight along the rocket (+z)
+    fn default() -> Self {
+        Self {
+            position: F64x3::zero(),
+            direction: F64x3::new(0.0, 0.0, 1.0),
+        }
+    }
+}
+
+impl Mount {
+    /// a motor at `position` (m from the center of mass), pushing along the rocket
+    pub fn at(position: F64x3) -> Self {
+        Self {
+            position,
+            ..Self::default()
+        }
+    }
+
+    pub fn direction(mut self, direction: F64x3) -> Self {
+        self.direction = direction / direction.norm();
+        self
+    }
+
+    /// tilts the thrust `angle` radians off of where it points now, towards the side at `azimuth` (radians around the body z axis, from +x)
+    pub fn misalignment(mut self, angle: f64, azimuth: f64) -> Self {
+        let axis = F64x3::new(-azimuth.sin(), azimuth.cos(), 0.0);
+        self.direction = Quaternion::from_axis_angle(axis, angle).rotate(self.direction);
+        self
+    }
+
+    /// force (N, body frame) from the given thrust
+    pub fn force(&self, thrust: f64) -> F64x3 {
+        self.direction * thrust
+    }
+
+    /// torque (Nm, body frame) about the center of mass from the given thrust
+    pub fn torque(&self, thrust: f64) -> F64x3 {
+        self.position.cross(self.force(thrust))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::DEG_TO_RAD;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn off_axis() {
+        // on the center line, only pushes
+        assert_eq!(Mount::default().torque(10.0), F64x3::zero());
+
+        // an outer motor of a cluster, 3cm out along +x and 20cm behind the center of mass, pitches the rocket about -y
+        let outer = Mount::at(F64x3::new(0.03, 0.0, -0.2));
+        assert_eq!(outer.force(10.0), F64x3::new(0.0, 0.0, 10.0));
+        let torque = outer.torque(10.0);
+        assert_approx_eq!(torque.y, -0.3);
+        assert_approx_eq!(torque.x, 0.0);
+
+        // a motor canted 1 degree towards +x, behind the center of mass, turns the nose towards -x
+        let canted = Mount::at(F64x3::new(0.0, 0.0, -0.2)).misalignment(DEG_TO_RAD, 0.0);
+        assert_approx_eq!(canted.direction.x, DEG_TO_RAD.sin());
+        assert_approx_eq!(canted.direction.norm(), 1.0);
+        assert!(canted.torque(10.0).y < 0.0);
+    }
+}
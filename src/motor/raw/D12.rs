@@ -25,3 +25,4 @@ pub const DATA: [[f64; 3]; 21] = [
 pub const DRY_WEIGHT: f64 = 21.5;
 pub const FUEL_WEIGHT: f64 = 21.1;
 pub const MOTOR_ID: &str = "D12";
+pub const DELAYS: [f64; 4] = [0.0, 3.0, 5.0, 7.0];
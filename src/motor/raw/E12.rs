@@ -36,3 +36,4 @@ pub const DATA: [[f64; 3]; 32] = [
 pub const DRY_WEIGHT: f64 = 24.0;
 pub const FUEL_WEIGHT: f64 = 35.9;
 pub const MOTOR_ID: &str = "E12";
+pub const DELAYS: [f64; 4] = [0.0, 4.0, 6.0, 8.0];
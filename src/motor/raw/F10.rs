@@ -32,3 +32,4 @@ pub const DATA: [[f64; 3]; 28] = [
 pub const DRY_WEIGHT: f64 = 43.39999999999999;
 pub const FUEL_WEIGHT: f64 = 40.7;
 pub const MOTOR_ID: &str = "F10";
+pub const DELAYS: [f64; 4] = [2.0, 4.0, 6.0, 8.0];
@@ -31,3 +31,4 @@ pub const DATA: [[f64; 3]; 27] = [
 pub const DRY_WEIGHT: f64 = 43.0;
 pub const FUEL_WEIGHT: f64 = 60.0;
 pub const MOTOR_ID: &str = "F15";
+pub const DELAYS: [f64; 4] = [0.0, 4.0, 6.0, 8.0];
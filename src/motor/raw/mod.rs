@@ -1,4 +1,4 @@
 #[allow(non_snake_case)] pub mod F10;
-#[allow(non_snake_case)] pub mod F15;
 #[allow(non_snake_case)] pub mod D12;
 #[allow(non_snake_case)] pub mod E12;
+#[allow(non_snake_case)] pub mod F15;
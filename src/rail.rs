@@ -0,0 +1,156 @@
+//! the launch rail (or rod) that guides a rocket until it is going fast enough to be stable
+
+use crate::math::{F64x3, Quaternion};
+use crate::secs;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LaunchRail {
+    /// how far the rocket travels along the rail before leaving it, m
+    pub length: f64,
+    /// angle of the rail above the horizon, radians (straight up is pi/2)
+    pub elevation: f64,
+    /// direction the rail leans in, radians clockwise from north (+y)
+    pub azimuth: f64,
+    /// coefficient of friction between the rocket and the rail
+    pub friction: f64,
+}
+
+/// when and how fast the rocket left the rail
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RailExit {
+    pub time: secs,
+    /// m/s
+    pub velocity: F64x3,
+}
+
+impl RailExit {
+    pub fn speed(&self) -> f64 {
+        self.velocity.norm()
+    }
+}
+
+impl LaunchRail {
+    /// a vertical rail with no friction
+    pub fn new(length: f64) -> Self {
+        Self {
+            length,
+            elevation: std::f64::consts::FRAC_PI_2,
+            azimuth: 0.0,
+            friction: 0.0,
+        }
+    }
+
+    pub fn elevation(mut self, elevation: f64) -> Self {
+        self.elevation = elevation;
+        self
+    }
+
+    pub fn azimuth(mut self, azimuth: f64) -> Self {
+        self.azimuth = azimuth;
+        self
+    }
+
+    pub fn friction(mut self, friction: f64) -> Self {
+        self.friction = friction;
+        self
+    }
+
+    /// unit vector pointing up the rail
+    pub fn direction(&self) -> F64x3 {
+        F64x3::new(
+            self.azimuth.sin() * self.elevation.cos(),
+            self.azimuth.cos() * self.elevation.cos(),
+            self.elevation.sin(),
+        )
+    }
+
+    /// orientation of a rocket sitting on the rail, with its nose (body +z) pointing up the rail
+    pub fn orientation(&self) -> Quaternion {
+        let tilt = Quaternion::from_axis_angle(
+            F64x3::new(1.0, 0.0, 0.0),
+            -(std::f64::consts::FRAC_PI_2 - self.elevation),
+        );
+        let turn = Quaternion::from_axis_angle(F64x3::new(0.0, 0.0, 1.0), -self.azimuth);
+        turn * tilt
+    }
+
+    /// how far along the rail something at `position` is, for a rail starting at `start`
+    pub fn travelled(&self, start: F64x3, position: F64x3) -> f64 {
+        (position - start).dot(self.direction())
+    }
+
+    /// the acceleration of a rocket held on the rail, from the acceleration it would have off of it
+    ///
+    /// the rail takes everything but the part along it, and friction comes from the weight of the rocket pressing
+    /// against the rail. the rocket can not slide back down the rail, it sits on the pad instead
+    pub fn constrain(&self, acceleration: F64x3, gravity: F64x3, velocity: F64x3) -> F64x3 {
+        let direction = self.direction();
+        let along = acceleration.dot(direction);
+        let normal = (gravity - direction * gravity.dot(direction)).norm();
+        let friction = self.friction * normal;
+
+        // once it is moving friction just slows it down, until then it has to be overcome
+        let along = if velocity.dot(direction) > 0.0 || along > friction {
+            along - friction
+        } else {
+            0.0
+        };
+        direction * along
+    }
+
+    /// finds where in a step from `before` to `after` (position, velocity) the rocket left the rail, if it did
+    pub fn exit(
+        &self,
+        start: F64x3,
+        time: secs,
+        dt: secs,
+        before: (F64x3, F64x3),
+        after: (F64x3, F64x3),
+    ) -> Option<RailExit> {
+        let travelled_before = self.travelled(start, before.0);
+        let travelled_after = self.travelled(start, after.0);
+        if travelled_before >= self.length || travelled_after < self.length {
+            return None;
+        }
+        let frac = (self.length - travelled_before) / (travelled_after - travelled_before);
+        Some(RailExit {
+            time: time + dt * frac,
+            velocity: before.1 + (after.1 - before.1) * frac,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::DEG_TO_RAD;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn direction() {
+        let rail = LaunchRail::new(1.0).elevation(80.0 * DEG_TO_RAD).azimuth(90.0 * DEG_TO_RAD);
+        let direction = rail.direction();
+        // leaning east
+        assert!(direction.x > 0.0);
+        assert_approx_eq!(direction.y, 0.0);
+        assert_approx_eq!(direction.z, (80.0 * DEG_TO_RAD).sin());
+        let nose = rail.orientation().rotate(F64x3::new(0.0, 0.0, 1.0));
+        for (a, b) in nose.into_iter().zip(direction) {
+            assert_approx_eq!(a, b);
+        }
+    }
+
+    #[test]
+    fn constrain() {
+        let rail = LaunchRail::new(1.0).elevation(45.0 * DEG_TO_RAD).friction(0.1);
+        let gravity = F64x3::new(0.0, 0.0, -10.0);
+        // sitting on the pad
+        assert_eq!(rail.constrain(gravity, gravity, F64x3::zero()), F64x3::zero());
+        // pushed up the rail, minus friction
+        let thrust = rail.direction() * 20.0;
+        let accel = rail.constrain(thrust + gravity, gravity, F64x3::zero());
+        let expected = 20.0 - 10.0 / 2f64.sqrt() - 0.1 * 10.0 / 2f64.sqrt();
+        assert_approx_eq!(accel.dot(rail.direction()), expected);
+        assert_approx_eq!(accel.norm(), expected);
+    }
+}
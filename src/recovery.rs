@@ -0,0 +1,193 @@
+//! recovery devices (parachutes and streamers), and when they are deployed
+
+use crate::events::{Event, FlightEvent};
+use crate::secs;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RecoveryKind {
+    /// diameter in m
+    Parachute { diameter: f64, cd: f64 },
+    /// length and width in m
+    Streamer { length: f64, width: f64, cd: f64 },
+}
+
+impl RecoveryKind {
+    /// drag coefficient times area (m^2) once fully open
+    pub fn drag_area(&self) -> f64 {
+        match *self {
+            Self::Parachute { diameter, cd } => cd * std::f64::consts::PI * (diameter / 2.0).powi(2),
+            Self::Streamer { length, width, cd } => cd * length * width,
+        }
+    }
+}
+
+/// when a recovery device comes out
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DeployTrigger {
+    Apogee,
+    /// on the way down, when the rocket falls past this height (m) above the launch site
+    Altitude(f64),
+    /// this many seconds after liftoff
+    Timer(secs),
+    /// when a motor's ejection charge fires
+    Ejection,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecoveryDevice {
+    pub name: String,
+    pub kind: RecoveryKind,
+    pub trigger: DeployTrigger,
+    /// how long it takes to fully open after it is deployed, the drag grows linearly until then
+    pub inflation_time: secs,
+    deployed_at: Option<secs>,
+}
+
+impl RecoveryDevice {
+    /// a parachute deployed at apogee
+    pub fn parachute(name: impl Into<String>, diameter: f64, cd: f64) -> Self {
+        Self::new(name, RecoveryKind::Parachute { diameter, cd })
+    }
+
+    /// a streamer deployed at apogee
+    pub fn streamer(name: impl Into<String>, length: f64, width: f64, cd: f64) -> Self {
+        Self::new(name, RecoveryKind::Streamer { length, width, cd })
+    }
+
+    fn new(name: impl Into<String>, kind: RecoveryKind) -> Self {
+        Self {
+            name: name.into(),
+            kind,
+            trigger: DeployTrigger::Apogee,
+            inflation_time: 0.5,
+            deployed_at: None,
+        }
+    }
+
+    pub fn deploy_at(mut self, trigger: DeployTrigger) -> Self {
+        self.trigger = trigger;
+        self
+    }
+
+    pub fn inflation_time(mut self, inflation_time: secs) -> Self {
+        self.inflation_time = inflation_time;
+        self
+    }
+
+    pub fn deployed_at(&self) -> Option<secs> {
+        self.deployed_at
+    }
+
+    /// deploys the device now, if it has not been already
+    pub fn deploy(&mut self, time: secs) -> Option<Event> {
+        if self.deployed_at.is_some() {
+            return None;
+        }
+        self.deployed_at = Some(time);
+        Some(Event {
+            time,
+            event: FlightEvent::Deploy(self.name.clone()),
+        })
+    }
+
+    /// drag coefficient times area (m^2) at the given time
+    pub fn drag_area(&self, time: secs) -> f64 {
+        match self.deployed_at {
+            Some(deployed) if time >= deployed => {
+                let open = if self.inflation_time > 0.0 {
+                    ((time - deployed) / self.inflation_time).min(1.0)
+                } else {
+                    1.0
+                };
+                self.kind.drag_area() * open
+            }
+            _ => 0.0,
+        }
+    }
+
+    /// checks the trigger after a step from `before` to `after` (time, altitude), deploying the device if it fired
+    ///
+    /// `events` is every event of the flight so far, including the ones from this step
+    pub fn update(&mut self, events: &[Event], before: (secs, f64), after: (secs, f64)) -> Option<Event> {
+        if self.deployed_at.is_some() {
+            return None;
+        }
+        let find = |wanted: FlightEvent| events.iter().find(|e| e.event == wanted).map(|e| e.time);
+        let time = match self.trigger {
+            DeployTrigger::Apogee => find(FlightEvent::Apogee),
+            DeployTrigger::Altitude(height) => find(FlightEvent::Apogee)
+                .filter(|_| after.1 <= height)
+                .map(|apogee| {
+                    if before.1 > height {
+                        before.0 + (after.0 - before.0) * (before.1 - height) / (before.1 - after.1)
+                    } else {
+                        // the apogee was already below the deploy height
+                        apogee.max(before.0)
+                    }
+                }),
+            DeployTrigger::Timer(delay) => find(FlightEvent::Liftoff)
+                .map(|liftoff| liftoff + delay)
+                .filter(|time| *time < after.0),
+            DeployTrigger::Ejection => events
+                .iter()
+                .find(|e| matches!(e.event, FlightEvent::Ejection(_)))
+                .map(|e| e.time),
+        }?;
+        self.deploy(time)
+    }
+
+    pub fn reset(&mut self) {
+        self.deployed_at = None;
+    }
+}
+
+/// the speed something falls at once drag balances its weight, m/s
+pub fn terminal_velocity(mass: f64, drag_area: f64, density: f64, gravity: f64) -> f64 {
+    (2.0 * mass * gravity.abs() / (density * drag_area)).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    fn event(time: secs, event: FlightEvent) -> Event {
+        Event { time, event }
+    }
+
+    #[test]
+    fn triggers() {
+        let events = vec![event(1.0, FlightEvent::Liftoff), event(5.5, FlightEvent::Apogee)];
+
+        let mut drogue = RecoveryDevice::streamer("drogue", 1.0, 0.1, 0.3);
+        assert!(drogue.update(&events[..1], (4.0, 90.0), (5.0, 100.0)).is_none());
+        assert_eq!(drogue.update(&events, (5.0, 100.0), (6.0, 100.0)).unwrap().time, 5.5);
+        // only once
+        assert!(drogue.update(&events, (6.0, 100.0), (7.0, 95.0)).is_none());
+
+        let mut main = RecoveryDevice::parachute("main", 1.0, 1.5).deploy_at(DeployTrigger::Altitude(50.0));
+        assert!(main.update(&events, (7.0, 95.0), (8.0, 60.0)).is_none());
+        assert_approx_eq!(main.update(&events, (8.0, 60.0), (9.0, 40.0)).unwrap().time, 8.5);
+
+        let mut timed = RecoveryDevice::parachute("timed", 1.0, 1.5).deploy_at(DeployTrigger::Timer(3.0));
+        assert!(timed.update(&events, (3.0, 50.0), (4.0, 90.0)).is_none());
+        assert_approx_eq!(timed.update(&events, (4.0, 90.0), (5.0, 100.0)).unwrap().time, 4.0);
+
+        let mut ejected = RecoveryDevice::parachute("ejected", 1.0, 1.5).deploy_at(DeployTrigger::Ejection);
+        assert!(ejected.update(&events, (6.0, 100.0), (7.0, 95.0)).is_none());
+        let events = [&events[..], &[event(7.2, FlightEvent::Ejection("main".into()))]].concat();
+        assert_eq!(ejected.update(&events, (7.0, 95.0), (8.0, 85.0)).unwrap().time, 7.2);
+    }
+
+    #[test]
+    fn inflation() {
+        let mut chute = RecoveryDevice::parachute("main", 2.0, 1.5).inflation_time(1.0);
+        assert_eq!(chute.drag_area(10.0), 0.0);
+        chute.deploy(10.0);
+        assert_eq!(chute.drag_area(9.0), 0.0);
+        assert_approx_eq!(chute.drag_area(10.5), 1.5 * std::f64::consts::PI / 2.0);
+        assert_approx_eq!(chute.drag_area(20.0), 1.5 * std::f64::consts::PI);
+        // a 1kg rocket under a 1.5 * pi drag area comes down at a bit under 2m/s
+        assert_approx_eq!(terminal_velocity(1.0, chute.drag_area(20.0), 1.225, 9.81), 1.84, 0.01);
+    }
+}
@@ -1,18 +1,116 @@
-use std::collections::HashMap;
+use std::collections::BTreeMap;
+use std::sync::Arc;
 
-use crate::math::F64x3;
-use crate::motor::Motor;
+use crate::aero::{Drag, DragCoefficient};
+use crate::atmosphere::Atmosphere;
+use crate::events::{self, Ejection, Event, EventDetector, EventListener, FlightEvent, FlightSample};
+use crate::gravity::{self, GravityModel};
+use crate::rail::{LaunchRail, RailExit};
+use crate::recovery::RecoveryDevice;
+use crate::stage::Stage;
+use crate::dispersion::Sample;
+use crate::integrator::{IntegrationMethod, Integrator, State, Euler};
+use crate::math::{F64x3, Quaternion, RAD_TO_DEG};
+use crate::motor::{Motor, Mount};
 use crate::logger::{CSVRow, Logger};
 use crate::consts::*;
 
+/// the part of the rocket that is integrated over time
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RocketState {
+    /// meters
+    pub position: F64x3,
+    /// m/s
+    pub velocity: F64x3,
+    /// rotation from the rocket's body frame to the world (the nose points along body +z)
+    pub orientation: Quaternion,
+    /// rad/s, in the body frame
+    pub angular_velocity: F64x3,
+}
+
+impl State for RocketState {
+    fn advance(&self, rate: &Self, dt: secs) -> Self {
+        Self {
+            position: self.position + rate.position * dt,
+            velocity: self.velocity + rate.velocity * dt,
+            orientation: self.orientation + rate.orientation * dt,
+            angular_velocity: self.angular_velocity + rate.angular_velocity * dt,
+        }
+    }
+
+    fn advance_velocity(&self, rate: &Self, dt: secs) -> Self {
+        Self {
+            velocity: self.velocity + rate.velocity * dt,
+            angular_velocity: self.angular_velocity + rate.angular_velocity * dt,
+            ..*self
+        }
+    }
+
+    fn drift(&self, dt: secs) -> Self {
+        let rate = self.angular_velocity.norm();
+        let orientation = if rate == 0.0 {
+            self.orientation
+        } else {
+            // the angular velocity is in the body frame, so the rotation is applied on the body side
+            self.orientation * Quaternion::from_axis_angle(self.angular_velocity / rate, rate * dt)
+        };
+        Self {
+            position: self.position + self.velocity * dt,
+            orientation,
+            ..*self
+        }
+    }
+
+    fn error(&self, other: &Self) -> f64 {
+        let position = (self.position - other.position).abs();
+        let velocity = (self.velocity - other.velocity).abs();
+        let angular_velocity = (self.angular_velocity - other.angular_velocity).abs();
+        let orientation = (self.orientation + other.orientation * -1.0).norm();
+        position
+            .into_iter()
+            .chain(velocity)
+            .chain(angular_velocity)
+            .fold(orientation, f64::max)
+    }
+
+    fn normalized(self) -> Self {
+        Self {
+            orientation: self.orientation.normalize(),
+            ..self
+        }
+    }
+}
+
+/// functions called with every event as it happens
+#[derive(Default)]
+struct EventListeners(Vec<EventListener>);
+
+impl std::fmt::Debug for EventListeners {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "EventListeners({})", self.0.len())
+    }
+}
+
 #[derive(Debug)]
 pub struct Rocket {
+    /// seconds since the rocket was created
+    time: secs,
     /// meters
     location: F64x3,
+    /// height (z) of the launch site, m. altitudes are measured from here
+    ground: f64,
     /// figure out
     velocity: F64x3,
     /// m/s^2
     acceleration: F64x3,
+    /// rotation from the body frame to the world
+    orientation: Quaternion,
+    /// rad/s, in the body frame
+    angular_velocity: F64x3,
+    /// principal moments of inertia (kg*m^2) around the body x, y, and z (roll) axes
+    ///
+    /// a moment of 0 locks rotation around that axis
+    inertia: F64x3,
     /// kg
     dry_mass: f64,
     /// kg (calculated with recalc_mass, not set manualy)
@@ -21,23 +119,74 @@ pub struct Rocket {
     /// 
     /// measured in newtons (kg*m)/second^2
     const_forces: Vec<F64x3>,
+    /// constant torques acting on the rocket, in the body frame
+    ///
+    /// measured in newton meters
+    const_torques: Vec<F64x3>,
     /// gravity. applied as an acceleration
-    /// 
-    /// TODO make this use a approximation with distance from sea level instead of just a constant acceleration
-    gravity: F64x3,
+    gravity: Arc<dyn GravityModel>,
+    drag: Drag,
+    /// velocity of the air around the rocket, m/s
+    wind: F64x3,
+    /// the air the rocket flies through, z is the height above the launch site
+    atmosphere: Atmosphere,
+    /// the rail the rocket starts on, and where it starts
+    rail: Option<(LaunchRail, F64x3)>,
+    rail_exit: Option<RailExit>,
+    /// parachutes and streamers
+    recovery: Vec<RecoveryDevice>,
+    detector: EventDetector,
+    /// every event so far, in order
+    events: Vec<Event>,
+    /// where the events from the last tick start in `events`
+    tick_events: usize,
+    /// how many events have been taken by `poll_events`
+    polled_events: usize,
+    listeners: EventListeners,
     /// Rocket Engines
-    engines: HashMap<String, Motor>,
+    engines: BTreeMap<String, Motor>,
+    /// where the engines are, engines that are not in here are on the center line
+    mounts: BTreeMap<String, Mount>,
+    /// stages still attached below this one, the bottom one first
+    stages: Vec<Stage>,
+    /// lights the engines this long after the last stage below separates
+    airstart_delay: Option<secs>,
+    /// stages that have separated, flying on their own
+    boosters: Vec<Rocket>,
+    /// ejection charges that have fired
+    ejections: Vec<Ejection>,
+    integration: IntegrationMethod,
+    integrator: Box<dyn Integrator<RocketState>>,
 }
 
 impl Rocket {
     pub fn recalc_mass(&mut self) {
+        self.mass = self.mass_at(self.time);
+    }
+
+    /// mass of the rocket (kg) at the given time, including any attached stages
+    fn mass_at(&self, time: secs) -> f64 {
         let mut mass = self.dry_mass;
         for engine in self.engines.values() {
-            mass += engine.weight();
+            mass += engine.weight_at(time);
         }
-        self.mass = mass;
+        for stage in &self.stages {
+            mass += stage.mass_at(time);
+        }
+        mass
+    }
+
+    /// every engine in the rocket, including the ones in attached stages
+    fn all_engines(&self) -> impl Iterator<Item = (&String, &Motor, Mount)> {
+        let own = self.engines.iter().map(|(name, engine)| (name, engine, self.mounts.get(name).copied().unwrap_or_default()));
+        let stages = self
+            .stages
+            .iter()
+            .flat_map(|stage| stage.engines.iter().map(move |(name, engine)| (name, engine, stage.mount(name))));
+        own.chain(stages)
     }
 
+    #[allow(clippy::single_match)]
     pub fn add_engine(&mut self, name: String, engine: Motor) {
         match self.engines.insert(name.clone(), engine) {
             Some(_) => {panic!("Engine {} already exists!", name)},
@@ -46,99 +195,442 @@ impl Rocket {
         self.recalc_mass();
     }
 
+    /// lights an engine, in this stage or any attached stage
     pub fn light_engine(&mut self, name: String) {
-        if let Some(engine) = self.engines.get_mut(&name) {
-            if !engine.lit() {
-                engine.light()
+        self.light_engine_at(name, self.time);
+    }
+
+    /// lights an engine at a time that may be in the future, for a delayed ignition
+    pub fn light_engine_at(&mut self, name: String, time: secs) {
+        let engines = std::iter::once(&mut self.engines).chain(self.stages.iter_mut().map(|stage| &mut stage.engines));
+        for engines in engines {
+            if let Some(engine) = engines.get_mut(&name) {
+                if !engine.lit() {
+                    engine.light(time)
+                }
+            }
+        }
+    }
+
+    pub fn time(&self) -> secs {
+        self.time
+    }
+
+    pub fn position(&self) -> F64x3 {
+        self.location
+    }
+
+    /// height above the launch site, m
+    pub fn altitude(&self) -> f64 {
+        self.location.z - self.ground
+    }
+
+    pub fn velocity(&self) -> F64x3 {
+        self.velocity
+    }
+
+    pub fn orientation(&self) -> Quaternion {
+        self.orientation
+    }
+
+    pub fn angular_velocity(&self) -> F64x3 {
+        self.angular_velocity
+    }
+
+    /// when and how fast the rocket left the launch rail, once it has
+    pub fn rail_exit(&self) -> Option<RailExit> {
+        self.rail_exit
+    }
+
+    /// every event so far, in the order they happened
+    pub fn events(&self) -> &[Event] {
+        &self.events
+    }
+
+    /// the events that happened since the last time this was called
+    pub fn poll_events(&mut self) -> impl Iterator<Item = &Event> {
+        let start = self.polled_events;
+        self.polled_events = self.events.len();
+        self.events[start..].iter()
+    }
+
+    /// calls `listener` with every event as it happens
+    pub fn on_event(&mut self, listener: impl FnMut(&Event) + Send + 'static) {
+        self.listeners.0.push(Box::new(listener));
+    }
+
+    /// the rocket has come back down, and is not simulated anymore
+    pub fn landed(&self) -> bool {
+        self.detector.landed()
+    }
+
+    /// the rocket, and every booster that came off it, have landed
+    pub fn all_landed(&self) -> bool {
+        self.landed() && self.boosters.iter().all(Rocket::all_landed)
+    }
+
+    /// the ejection charges that have fired so far, with how fast the rocket was going when they did
+    pub fn ejections(&self) -> &[Ejection] {
+        &self.ejections
+    }
+
+    /// stages that have separated from the rocket, in the order they did
+    pub fn boosters(&self) -> &[Rocket] {
+        &self.boosters
+    }
+
+    pub fn boosters_mut(&mut self) -> &mut [Rocket] {
+        &mut self.boosters
+    }
+
+    /// dynamic pressure (Pa) on the rocket in the given state
+    fn dynamic_pressure(&self, state: &RocketState) -> f64 {
+        let air = self.atmosphere.at(state.position.z - self.ground);
+        0.5 * air.density * (state.velocity - self.wind).norm().powi(2)
+    }
+
+    /// the launch rail, if the rocket is still on it
+    fn on_rail(&self, state: &RocketState) -> Option<&LaunchRail> {
+        match &self.rail {
+            Some((rail, start)) if self.rail_exit.is_none() && rail.travelled(*start, state.position) < rail.length => {
+                Some(rail)
             }
+            _ => None,
+        }
+    }
+
+    fn state(&self) -> RocketState {
+        RocketState {
+            position: self.location,
+            velocity: self.velocity,
+            orientation: self.orientation,
+            angular_velocity: self.angular_velocity,
         }
     }
 
-    pub fn log(&mut self, time: f64, logger: &mut Logger) {
+    pub fn log(&mut self, logger: &mut Logger) {
+        let state = self.state();
+        let gravity = self.gravity.acceleration(self.location);
+        let euler = self.orientation.to_euler();
         logger.write_record(CSVRow {
-            time,
+            time: self.time,
             pos_x: self.location.z,
             pos_y: self.location.x,
             pos_z: self.location.y,
             accel_x: self.acceleration.x,
             accel_y: self.acceleration.y,
             accel_z: self.acceleration.z,
-            accel_ng_x: self.acceleration.x - GRAVITY.x,
-            accel_ng_y: self.acceleration.y - GRAVITY.y,
-            accel_ng_z: self.acceleration.z - GRAVITY.z,
+            accel_ng_x: self.acceleration.x - gravity.x,
+            accel_ng_y: self.acceleration.y - gravity.y,
+            accel_ng_z: self.acceleration.z - gravity.z,
             vel_x: self.velocity.x,
             vel_y: self.velocity.y,
             vel_z: self.velocity.z,
             mass: self.mass * KG_TO_GRAMS,
+            quat_w: self.orientation.w(),
+            quat_x: self.orientation.x(),
+            quat_y: self.orientation.y(),
+            quat_z: self.orientation.z(),
+            roll: euler.z * RAD_TO_DEG,
+            pitch: euler.x * RAD_TO_DEG,
+            yaw: euler.y * RAD_TO_DEG,
+            ang_vel_x: self.angular_velocity.x,
+            ang_vel_y: self.angular_velocity.y,
+            ang_vel_z: self.angular_velocity.z,
+            drag: self.calc_drag(self.time, &state).norm(),
+            event: self.events[self.tick_events..].iter().map(|e| e.to_string()).collect::<Vec<_>>().join(" "),
         }).unwrap();
     }
 }
 
 impl Rocket {
-    fn calc_engine_thrusts(&mut self, dt: secs) -> F64x3 {
-        let mut total = F64x3::zero();
+    /// drops the bottom stage if its separation trigger fired, and lights the next one if it airstarts
+    fn separate_stages(&mut self) {
+        let Some(separation) = self.stages.first().and_then(|stage| stage.separation_time(&self.events, self.time)) else {
+            return;
+        };
+        let stage = self.stages.remove(0);
+        let event = Event {
+            time: separation,
+            event: FlightEvent::Separation(stage.name.clone()),
+        };
+
+        // the booster carries on from where the rocket is now, with everything else about the flight the same
+        let mut factory = RocketFactory::with_mass(stage.mass)
+            .inertia(stage.inertia)
+            .orientation(self.orientation)
+            .wind(self.wind)
+            .atmosphere(self.atmosphere)
+            .integrator(self.integration);
+        factory.drag = stage.drag;
+        factory.gravity = self.gravity.clone();
+        factory.engines = stage.engines;
+        factory.mounts = stage.mounts;
+        factory.recovery = stage.recovery;
+        let mut booster = factory.at(self.location);
+        booster.time = self.time;
+        booster.velocity = self.velocity;
+        booster.angular_velocity = self.angular_velocity;
+        booster.ground = self.ground;
+        booster.recalc_mass();
+        booster.detector = EventDetector::in_flight();
+        booster.events.push(event.clone());
+        self.boosters.push(booster);
+        self.events.push(event);
+
+        match self.stages.first_mut() {
+            Some(next) => {
+                if let Some(delay) = next.airstart_delay {
+                    next.light(separation + delay);
+                }
+            }
+            None => {
+                if let Some(delay) = self.airstart_delay {
+                    self.engines.values_mut().filter(|e| !e.lit()).for_each(|e| e.light(separation + delay));
+                }
+            }
+        }
+        self.recalc_mass();
+    }
+
+    /// force and torque (body frame) of all engines at `t`, averaged over the next `window` so the full impulse of the engines is delivered
+    fn calc_engine_thrusts(&self, t: secs, window: secs) -> (F64x3, F64x3) {
+        let mut force = F64x3::zero();
+        let mut torque = F64x3::zero();
 
-        for engine in self.engines.values_mut() {
-            total += engine.thrust_for(dt).unwrap_or(F64x3::zero());
+        for (_, engine, mount) in self.all_engines() {
+            let thrust = if window > 0.0 {
+                engine.average_thrust(t, t + window)
+            } else {
+                engine
+                    .time_since_ignition(t)
+                    .map_or(0.0, |since| engine.thrust_at(since))
+            };
+            force += mount.force(thrust);
+            torque += mount.torque(thrust);
         }
 
-        total
+        (force, torque)
+    }
+
+    /// drag force on the rocket (and any deployed recovery devices) in the given state, from its velocity relative to the wind
+    fn calc_drag(&self, t: secs, state: &RocketState) -> F64x3 {
+        let air = self.atmosphere.at(state.position.z - self.ground);
+        let air_velocity = state.velocity - self.wind;
+        let recovery_area: f64 = self.recovery.iter().map(|device| device.drag_area(t)).sum();
+        self.stack_drag().force(air_velocity, &air) + air_velocity * (-0.5 * air.density * air_velocity.norm() * recovery_area)
+    }
+
+    /// the drag of the widest part of the stack
+    fn stack_drag(&self) -> &Drag {
+        self.stages
+            .iter()
+            .map(|stage| &stage.drag)
+            .fold(&self.drag, |widest, drag| if drag.reference_area > widest.reference_area { drag } else { widest })
+    }
+
+    /// normal force and drag on the rocket's body (not the recovery devices) in the given state, in the body frame
+    fn calc_body_aero(&self, state: &RocketState) -> (F64x3, F64x3) {
+        let air = self.atmosphere.at(state.position.z - self.ground);
+        let air_velocity = state.orientation.conj().rotate(state.velocity - self.wind);
+        (self.drag.normal_force(air_velocity, &air), self.stack_drag().force(air_velocity, &air))
     }
 
-    fn calc_accel(&mut self, dt: secs) {
+    /// acceleration of the rocket in the given state
+    fn calc_accel(&self, t: secs, window: secs, state: &RocketState) -> F64x3 {
         // acceleration = force / mass, so sum up all constant forces, and then devide them by the mass of the rocket
         let mut total_force: F64x3 = F64x3::zero();
 
         self.const_forces.iter().for_each(|i| {total_force += *i});
 
-        let engine_thrust = self.calc_engine_thrusts(dt);
+        // engines push along the rocket, wherever it is pointing
+        let engine_thrust = state.orientation.rotate(self.calc_engine_thrusts(t, window).0);
         total_force += engine_thrust;
 
-        self.recalc_mass();
+        total_force += self.calc_drag(t, state);
+        total_force += state.orientation.rotate(self.calc_body_aero(state).0);
 
-        self.acceleration = (total_force / self.mass) + self.gravity;
+        (total_force / self.mass_at(t)) + self.gravity.acceleration(state.position)
     }
 
-    fn calc_velocity(&mut self, dt: secs) {
-        // acceleration is velocity/time, so diff in velocity for a given period of time is acceleration * time
-        let diff_in_velocity = self.acceleration * dt; 
-        // add this new velocity to the rocket
-        self.velocity += diff_in_velocity;
+    /// angular acceleration of the rocket in the given state, in the body frame
+    fn calc_angular_accel(&self, t: secs, window: secs, state: &RocketState) -> F64x3 {
+        // engines off the center line (or pointing off of it) twist the rocket
+        let mut total_torque = self.calc_engine_thrusts(t, window).1;
+
+        self.const_torques.iter().for_each(|i| {total_torque += *i});
+
+        // the air pushing at the cp, away from the center of mass
+        let (normal, drag) = self.calc_body_aero(state);
+        total_torque += self.drag.torque(normal + drag);
+
+        // eulers equations, torque = I * angular acceleration + angular velocity x (I * angular velocity)
+        let w = state.angular_velocity;
+        let gyroscopic = w.cross(self.inertia * w);
+        let accel = total_torque - gyroscopic;
+        F64x3::new(
+            if self.inertia.x > 0.0 { accel.x / self.inertia.x } else { 0.0 },
+            if self.inertia.y > 0.0 { accel.y / self.inertia.y } else { 0.0 },
+            if self.inertia.z > 0.0 { accel.z / self.inertia.z } else { 0.0 },
+        )
     }
 
-    fn calc_location(&mut self, dt: secs) {
-        // velocity is change in location / change in time, so velocity * time = change in location
-        let change_in_location = self.velocity * dt;
-        // move the rocket accordingly
-        self.location += change_in_location;
+    /// rate of change of the rocket's state (velocity is the change in location / change in time, and acceleration the change in velocity / time)
+    fn rate(&self, t: secs, window: secs, state: &RocketState) -> RocketState {
+        if let Some(rail) = self.on_rail(state) {
+            // the rail holds the rocket straight
+            let gravity = self.gravity.acceleration(state.position);
+            return RocketState {
+                position: state.velocity,
+                velocity: rail.constrain(self.calc_accel(t, window, state), gravity, state.velocity),
+                orientation: Quaternion::new(0.0, 0.0, 0.0, 0.0),
+                angular_velocity: F64x3::zero(),
+            };
+        }
+        RocketState {
+            position: state.velocity,
+            velocity: self.calc_accel(t, window, state),
+            // derivative of a quaternion rotated by a body frame angular velocity
+            orientation: state.orientation * Quaternion::from(state.angular_velocity) * 0.5,
+            angular_velocity: self.calc_angular_accel(t, window, state),
+        }
     }
 
     pub fn tick(&mut self, dt: secs) {
-        self.calc_accel(dt);
-        self.calc_velocity(dt);
-        self.calc_location(dt);
+        for booster in &mut self.boosters {
+            booster.tick(dt);
+        }
+        self.tick_events = self.events.len();
+        if self.landed() {
+            // sitting on the ground
+            self.time += dt;
+            return;
+        }
+
+        let state = self.state();
+        // the integrator is taken out while it runs, so the rate function can borrow the rest of the rocket
+        let mut integrator = std::mem::replace(&mut self.integrator, Box::new(Euler));
+        let next = integrator.integrate(&state, self.time, dt, &mut |t, window, s| self.rate(t, window, s));
+        self.integrator = integrator;
+
+        // average acceleration over the step
+        self.acceleration = (next.velocity - state.velocity) / dt;
+        self.location = next.position;
+        self.velocity = next.velocity;
+        self.orientation = next.orientation;
+        self.angular_velocity = next.angular_velocity;
+
+        let mut events = vec![];
+        for (name, engine, _) in self.all_engines() {
+            events.extend(events::motor_events(name, engine, self.time, self.time + dt));
+        }
+        for event in &events {
+            if let FlightEvent::Ejection(motor) = &event.event {
+                let frac = (event.time - self.time) / dt;
+                let velocity = state.velocity + (next.velocity - state.velocity) * frac;
+                let impulse = self.all_engines().find(|(name, _, _)| *name == motor).map_or(0.0, |(_, e, _)| e.ejection_impulse());
+                // pushed along the rocket as it was pointing when the charge fired, not at the end of the step
+                let orientation = state.orientation * (state.orientation.conj() * next.orientation).fractional(frac);
+                self.velocity += orientation.rotate(F64x3::new(0.0, 0.0, impulse / self.mass_at(event.time)));
+                self.ejections.push(Ejection { motor: motor.clone(), time: event.time, velocity });
+            }
+        }
+        if let (Some((rail, start)), None) = (&self.rail, self.rail_exit) {
+            self.rail_exit = rail.exit(*start, self.time, dt, (state.position, state.velocity), (next.position, next.velocity));
+            if let Some(exit) = self.rail_exit {
+                events.push(Event { time: exit.time, event: FlightEvent::RailExit });
+            }
+        }
+        self.time += dt;
+        self.recalc_mass();
+
+        events.extend(self.detector.update(FlightSample {
+            time: self.time,
+            altitude: next.position.z - self.ground,
+            vertical_velocity: next.velocity.z,
+            dynamic_pressure: self.dynamic_pressure(&next),
+        }));
+        if self.landed() {
+            self.location.z = self.ground;
+            self.velocity = F64x3::zero();
+            self.angular_velocity = F64x3::zero();
+        }
+
+        let start = self.events.len();
+        self.events.extend(events);
+        self.separate_stages();
+        for device in &mut self.recovery {
+            if let Some(deploy) = device.update(&self.events, (self.time - dt, state.position.z - self.ground), (self.time, next.position.z - self.ground)) {
+                self.events.push(deploy);
+            }
+        }
+
+        self.events[start..].sort_by(|a, b| a.time.total_cmp(&b.time));
+        for event in &self.events[start..] {
+            self.listeners.0.iter_mut().for_each(|listener| listener(event));
+        }
     }
 }
 
+#[derive(Clone)]
 pub struct RocketFactory {
     /// rocket mass in grams
     mass: f64,
-    engines: HashMap<String, Motor>,
+    engines: BTreeMap<String, Motor>,
+    mounts: BTreeMap<String, Mount>,
     const_forces: Vec<F64x3>,
+    const_torques: Vec<F64x3>,
+    /// kg*m^2
+    inertia: F64x3,
+    orientation: Quaternion,
+    drag: Drag,
+    wind: F64x3,
+    atmosphere: Atmosphere,
+    gravity: Arc<dyn GravityModel>,
+    rail: Option<LaunchRail>,
+    recovery: Vec<RecoveryDevice>,
+    stages: Vec<Stage>,
+    airstart_delay: Option<secs>,
+    integration: IntegrationMethod,
 }
 
 impl RocketFactory {
     /// constructs a new rocket at the given location
     pub fn at(&self, location: F64x3) -> Rocket {
         let mut r = Rocket {
+            time: 0.0,
             location,
+            ground: location.z,
             velocity: F64x3::zero(),
             acceleration: F64x3::zero(),
+            // the rail decides which way the rocket points
+            orientation: self.rail.map_or(self.orientation, |rail| rail.orientation()),
+            angular_velocity: F64x3::zero(),
+            inertia: self.inertia,
             dry_mass: self.mass * GRAMS_TO_KG, 
             mass: 0.0,
             const_forces: self.const_forces.clone(),
-            gravity: GRAVITY,
+            const_torques: self.const_torques.clone(),
+            gravity: self.gravity.clone(),
+            drag: self.drag.clone(),
+            wind: self.wind,
+            atmosphere: self.atmosphere,
+            rail: self.rail.map(|rail| (rail, location)),
+            rail_exit: None,
+            recovery: self.recovery.clone(),
+            detector: EventDetector::new(),
+            events: vec![],
+            tick_events: 0,
+            polled_events: 0,
+            listeners: EventListeners::default(),
             engines: self.engines.clone(),
+            mounts: self.mounts.clone(),
+            stages: self.stages.clone(),
+            airstart_delay: self.airstart_delay,
+            boosters: vec![],
+            ejections: vec![],
+            integration: self.integration,
+            integrator: self.integration.build(),
         };
         r.recalc_mass();
         r
@@ -148,8 +640,21 @@ impl RocketFactory {
     pub fn with_mass(mass: f64) -> Self {
         Self {
             mass,
-            engines: HashMap::new(),
+            engines: BTreeMap::new(),
+            mounts: BTreeMap::new(),
             const_forces: vec![],
+            const_torques: vec![],
+            inertia: F64x3::zero(),
+            orientation: Quaternion::default(),
+            drag: Drag::default(),
+            wind: F64x3::zero(),
+            atmosphere: Atmosphere::standard(),
+            gravity: Arc::new(gravity::Constant::default()),
+            rail: None,
+            recovery: vec![],
+            stages: vec![],
+            airstart_delay: None,
+            integration: IntegrationMethod::default(),
         }
     }
 
@@ -157,8 +662,21 @@ impl RocketFactory {
         self.mass = mass;
     }
 
-    pub fn add_engine(mut self, name: String, engine: Motor) -> Self {
-        self.engines.insert(name, engine);
+    /// adds an engine, either a [`Motor`] or motor data from the [`MotorCatalog`](crate::motor::MotorCatalog)
+    pub fn add_engine(mut self, name: String, engine: impl Into<Motor>) -> Self {
+        self.engines.insert(name, engine.into());
+        self
+    }
+
+    /// adds an engine at a position (and direction) in the rocket, for clusters or misaligned motors
+    pub fn add_engine_at(mut self, name: String, engine: impl Into<Motor>, mount: Mount) -> Self {
+        self.mounts.insert(name.clone(), mount);
+        self.add_engine(name, engine)
+    }
+
+    /// the integrator used to step the rocket's flight
+    pub fn integrator(mut self, method: IntegrationMethod) -> Self {
+        self.integration = method;
         self
     }
 
@@ -166,4 +684,431 @@ impl RocketFactory {
         self.const_forces.push(force);
         self
     }
+
+    /// adds a constant torque (newton meters), in the body frame
+    pub fn add_const_torque(mut self, torque: F64x3) -> Self {
+        self.const_torques.push(torque);
+        self
+    }
+
+    /// principal moments of inertia (kg*m^2) around the body x, y, and z (roll) axes.
+    /// until this is set the rocket does not rotate
+    pub fn inertia(mut self, inertia: F64x3) -> Self {
+        self.inertia = inertia;
+        self
+    }
+
+    /// reference area (m^2) and drag coefficient, either a constant or a [`DragCoefficient::Mach`] table
+    pub fn drag(mut self, reference_area: f64, coefficient: impl Into<DragCoefficient>) -> Self {
+        self.drag = Drag {
+            reference_area,
+            coefficient: coefficient.into(),
+            ..self.drag
+        };
+        self
+    }
+
+    /// normal force coefficient slope (CN alpha, per radian) of the whole rocket, how hard the air pushes it sideways
+    /// at an angle of attack
+    pub fn normal_coefficient(mut self, normal_coefficient: f64) -> Self {
+        self.drag.normal_coefficient = normal_coefficient;
+        self
+    }
+
+    /// center of pressure of the whole rocket, m from the center of mass (body frame). behind the center of mass (-z)
+    /// the rocket turns into the wind
+    pub fn cp(mut self, cp: F64x3) -> Self {
+        self.drag.cp = cp;
+        self
+    }
+
+    /// constant wind, m/s. drag is calculated from the rockets velocity relative to it
+    pub fn wind(mut self, wind: F64x3) -> Self {
+        self.wind = wind;
+        self
+    }
+
+    /// conditions at the launch site, the standard atmosphere at sea level by default
+    pub fn atmosphere(mut self, atmosphere: Atmosphere) -> Self {
+        self.atmosphere = atmosphere;
+        self
+    }
+
+    /// the gravity model, standard gravity everywhere by default
+    pub fn gravity(mut self, gravity: impl GravityModel + 'static) -> Self {
+        self.gravity = Arc::new(gravity);
+        self
+    }
+
+    /// starts the rocket on a launch rail, which points it along the rail (instead of the [`orientation`](Self::orientation))
+    /// and holds it there until it has travelled the length of the rail
+    pub fn launch_rail(mut self, rail: LaunchRail) -> Self {
+        self.rail = Some(rail);
+        self
+    }
+
+    /// adds a parachute or streamer. for dual deploy, add a drogue deployed at apogee and a main deployed at an altitude
+    pub fn add_recovery(mut self, device: RecoveryDevice) -> Self {
+        self.recovery.push(device);
+        self
+    }
+
+    /// adds a stage below the rocket. stages are added in the order they fire, so the first one added is at the bottom
+    ///
+    /// everything else set on the factory is the final (top) stage
+    pub fn add_stage(mut self, stage: Stage) -> Self {
+        self.stages.push(stage);
+        self
+    }
+
+    /// lights the top stage's engines this long after the last stage below it separates (an airstart)
+    pub fn airstart_delay(mut self, delay: secs) -> Self {
+        self.airstart_delay = Some(delay);
+        self
+    }
+
+    /// a copy of the factory, changed by a monte carlo sample
+    pub fn dispersed(&self, sample: &Sample) -> Self {
+        let mut factory = self.clone();
+        factory.mass *= sample.mass_scale;
+        factory.drag.coefficient = factory.drag.coefficient.scaled(sample.drag_scale);
+        for stage in &mut factory.stages {
+            stage.mass *= sample.mass_scale;
+            stage.drag.coefficient = stage.drag.coefficient.scaled(sample.drag_scale);
+        }
+        for engines in std::iter::once(&mut factory.engines).chain(factory.stages.iter_mut().map(|stage| &mut stage.engines)) {
+            for engine in engines.values_mut() {
+                *engine = engine.clone().with_impulse_scale(sample.impulse_scale);
+            }
+        }
+        factory.wind = sample.wind(factory.wind);
+        match factory.rail {
+            Some(rail) => factory.rail = Some(sample.rail(rail)),
+            None => factory.orientation = sample.tilt() * factory.orientation,
+        }
+        factory
+    }
+
+    /// the orientation the rocket starts in, the nose is body +z
+    pub fn orientation(mut self, orientation: Quaternion) -> Self {
+        self.orientation = orientation;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::integrator::IntegrationMethod;
+    use crate::math::DEG_TO_RAD;
+    use crate::motor::{MotorCatalog, Mount};
+    use crate::recovery::{terminal_velocity, DeployTrigger, RecoveryKind};
+    use crate::stage::Stage;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn torque_spins_up() {
+        let mut rocket = RocketFactory::with_mass(1000.0)
+            .inertia(F64x3::new(0.5, 0.5, 0.01))
+            .add_const_torque(F64x3::new(0.1, 0.0, 0.0))
+            .add_const_force(F64x3::new(0.0, 0.0, 9.80665))
+            .integrator(IntegrationMethod::RK4)
+            .at(F64x3::zero());
+        for _ in 0..100 {
+            rocket.tick(0.01);
+        }
+        // 0.1Nm / 0.5kgm^2 for a second
+        assert_approx_eq!(rocket.angular_velocity().x, 0.2);
+        let pitch = rocket.orientation().to_euler().x;
+        assert_approx_eq!(pitch, 0.1, 1e-6);
+    }
+
+    #[test]
+    fn thrust_follows_the_body() {
+        let tilted = Quaternion::from_axis_angle(F64x3::new(1.0, 0.0, 0.0), 30.0 * DEG_TO_RAD);
+        let mut rocket = RocketFactory::with_mass(500.0)
+            .add_engine("main".into(), MotorCatalog::builtin().find("F15").unwrap())
+            .orientation(tilted)
+            .at(F64x3::zero());
+        rocket.light_engine("main".into());
+        for _ in 0..10 {
+            rocket.tick(0.05);
+        }
+        let velocity = rocket.velocity();
+        // rotating +z around +x pushes towards -y
+        assert!(velocity.y < 0.0);
+        assert_approx_eq!(velocity.x, 0.0);
+        // only thrust pushes sideways
+        let sideways = -velocity.y;
+        let up = velocity.z + 9.80665 * rocket.time();
+        assert_approx_eq!(sideways / up, (30.0 * DEG_TO_RAD).tan(), 1e-6);
+    }
+
+    #[test]
+    fn drag_slows_the_rocket() {
+        let factory = RocketFactory::with_mass(500.0)
+            .add_engine("main".into(), MotorCatalog::builtin().find("F15").unwrap())
+            .integrator(IntegrationMethod::RK4);
+        let apogee = |factory: &RocketFactory| {
+            let mut rocket = factory.at(F64x3::zero());
+            rocket.light_engine("main".into());
+            while rocket.velocity().z >= 0.0 {
+                rocket.tick(0.01);
+            }
+            rocket.position().z
+        };
+        let vacuum = apogee(&factory);
+        let factory = factory.drag(0.0025, 0.5);
+        let with_drag = apogee(&factory);
+        assert!(with_drag < vacuum * 0.9, "{} {}", with_drag, vacuum);
+        // wind pushes the rocket along with it
+        let mut drifting = factory.wind(F64x3::new(5.0, 0.0, 0.0)).at(F64x3::zero());
+        drifting.tick(0.1);
+        assert!(drifting.velocity().x > 0.0);
+    }
+
+    #[test]
+    fn weathercocking() {
+        let nose = |cp: F64x3| {
+            let mut rocket = RocketFactory::with_mass(500.0)
+                .add_engine("main".into(), MotorCatalog::builtin().find("F15").unwrap())
+                .inertia(F64x3::new(0.05, 0.05, 0.001))
+                .drag(0.0025, 0.5)
+                .normal_coefficient(8.0)
+                .cp(cp)
+                .wind(F64x3::new(5.0, 0.0, 0.0))
+                .at(F64x3::zero());
+            rocket.light_engine("main".into());
+            for _ in 0..100 {
+                rocket.tick(0.01);
+            }
+            rocket.orientation().rotate(F64x3::new(0.0, 0.0, 1.0))
+        };
+        // with the cp on the center of mass the wind only pushes it along
+        let balanced = nose(F64x3::zero());
+        assert_approx_eq!(balanced.x, 0.0);
+        // with it behind, the nose turns upwind
+        let stable = nose(F64x3::new(0.0, 0.0, -0.1));
+        assert!(stable.x < -0.05, "{:?}", stable);
+        // and in front, it turns downwind
+        let unstable = nose(F64x3::new(0.0, 0.0, 0.1));
+        assert!(unstable.x > 0.05, "{:?}", unstable);
+    }
+
+    #[test]
+    fn leaves_the_rail() {
+        let rail = LaunchRail::new(1.0).elevation(80.0 * DEG_TO_RAD).friction(0.2);
+        let mut rocket = RocketFactory::with_mass(500.0)
+            .add_engine("main".into(), MotorCatalog::builtin().find("F15").unwrap())
+            .inertia(F64x3::new(0.05, 0.05, 0.001))
+            .add_const_torque(F64x3::new(0.01, 0.0, 0.0))
+            .launch_rail(rail)
+            .integrator(IntegrationMethod::RK4)
+            .at(F64x3::zero());
+        // sits on the pad until it is lit
+        rocket.tick(0.1);
+        assert_eq!(rocket.position(), F64x3::zero());
+        rocket.light_engine("main".into());
+        while rocket.rail_exit().is_none() {
+            rocket.tick(0.01);
+            // and is held straight until it leaves
+            if rocket.rail_exit().is_none() {
+                assert_eq!(rocket.angular_velocity(), F64x3::zero());
+                let sideways = rocket.position() - rail.direction() * rail.travelled(F64x3::zero(), rocket.position());
+                assert!(sideways.norm() < 1e-9);
+            }
+        }
+        let exit = rocket.rail_exit().unwrap();
+        assert!(exit.time > 0.1 && exit.time < rocket.time());
+        assert!(exit.speed() > 5.0 && exit.speed() < 20.0, "{:?}", exit);
+        // the exit velocity is along the rail
+        assert_approx_eq!(exit.velocity.normalize().dot(rail.direction()), 1.0);
+    }
+
+    #[test]
+    fn lands_at_the_launch_site() {
+        // a pad on a hill, 100m up in the frame
+        let mut rocket = RocketFactory::with_mass(500.0)
+            .add_engine("main".into(), MotorCatalog::builtin().find("F15").unwrap())
+            .drag(0.0025, 0.5)
+            .at(F64x3::new(0.0, 0.0, 100.0));
+        rocket.light_engine("main".into());
+        while !rocket.landed() {
+            rocket.tick(0.01);
+        }
+        assert_eq!(rocket.position().z, 100.0);
+        assert_eq!(rocket.altitude(), 0.0);
+        assert!(rocket.time() > 5.0);
+    }
+
+    #[test]
+    fn flight_events() {
+        let mut rocket = RocketFactory::with_mass(500.0)
+            .add_engine("main".into(), MotorCatalog::builtin().find("F15").unwrap())
+            .drag(0.0025, 0.5)
+            .launch_rail(LaunchRail::new(1.0))
+            .integrator(IntegrationMethod::RK4)
+            .at(F64x3::zero());
+        let (tx, rx) = std::sync::mpsc::channel();
+        rocket.on_event(move |event| tx.send(event.clone()).unwrap());
+
+        rocket.tick(0.05);
+        rocket.light_engine("main".into());
+        while !rocket.landed() {
+            rocket.tick(0.05);
+            assert!(rocket.time() < 60.0);
+        }
+        let names: Vec<String> = rocket.poll_events().map(|e| e.event.to_string()).collect();
+        assert_eq!(
+            names,
+            vec!["ignition(main)", "liftoff", "rail_exit", "max_q", "burnout(main)", "apogee", "landing"]
+        );
+        assert_eq!(rocket.poll_events().count(), 0);
+        assert_eq!(rx.try_iter().collect::<Vec<_>>(), rocket.events());
+
+        let events = rocket.events();
+        assert_approx_eq!(events[0].time, 0.05);
+        let burn_time = Motor::from(MotorCatalog::builtin().find("F15").unwrap()).burn_time();
+        assert_approx_eq!(events[4].time, 0.05 + burn_time);
+        // resting on the ground
+        assert_eq!(rocket.position().z, 0.0);
+        assert_eq!(rocket.velocity(), F64x3::zero());
+    }
+
+    #[test]
+    fn dual_deploy() {
+        let mass = 0.5;
+        let main = RecoveryDevice::parachute("main", 0.6, 1.5).deploy_at(DeployTrigger::Altitude(30.0));
+        let mut rocket = RocketFactory::with_mass(mass * KG_TO_GRAMS)
+            .add_engine("main".into(), MotorCatalog::builtin().find("F15").unwrap())
+            .drag(0.0025, 0.5)
+            .add_recovery(RecoveryDevice::streamer("drogue", 1.0, 0.1, 0.3))
+            .add_recovery(main.clone())
+            .wind(F64x3::new(3.0, 0.0, 0.0))
+            .launch_rail(LaunchRail::new(1.0))
+            .integrator(IntegrationMethod::RK4)
+            .at(F64x3::zero());
+        rocket.light_engine("main".into());
+        let mut descent_rate = 0.0;
+        while !rocket.landed() {
+            descent_rate = rocket.velocity().z;
+            rocket.tick(0.01);
+            assert!(rocket.time() < 120.0);
+        }
+        let names: Vec<String> = rocket.events().iter().map(|e| e.event.to_string()).collect();
+        assert!(names.ends_with(&["apogee".into(), "deploy(drogue)".into(), "deploy(main)".into(), "landing".into()]));
+
+        // comes down at about the terminal velocity under the main (the empty motor is still in the rocket)
+        let dry_mass = mass + MotorCatalog::builtin().find("F15").unwrap().dry_weight() * GRAMS_TO_KG;
+        let terminal = terminal_velocity(
+            dry_mass,
+            main.kind.drag_area() + RecoveryKind::Streamer { length: 1.0, width: 0.1, cd: 0.3 }.drag_area() + 0.0025 * 0.5,
+            Atmosphere::standard().at(0.0).density,
+            9.80665,
+        );
+        assert_approx_eq!(-descent_rate, terminal, 0.05);
+        // and drifts with the wind
+        assert!(rocket.position().x > 10.0);
+    }
+
+    #[test]
+    fn two_stage() {
+        let catalog = MotorCatalog::builtin();
+        let booster = Stage::new("booster", 300.0)
+            .add_engine("first".into(), catalog.find("F15").unwrap())
+            .drag(0.0025, 0.5)
+            .add_recovery(RecoveryDevice::streamer("booster_streamer", 1.0, 0.1, 0.3));
+        let mut rocket = RocketFactory::with_mass(400.0)
+            .add_engine("second".into(), catalog.find("E12").unwrap())
+            .drag(0.0015, 0.45)
+            .add_stage(booster)
+            .airstart_delay(0.5)
+            .add_recovery(RecoveryDevice::parachute("chute", 0.6, 1.5))
+            .integrator(IntegrationMethod::RK4)
+            .at(F64x3::zero());
+        rocket.light_engine("first".into());
+        while !rocket.all_landed() {
+            rocket.tick(0.01);
+            assert!(rocket.time() < 300.0);
+        }
+
+        let burn_time = Motor::from(catalog.find("F15").unwrap()).burn_time();
+        let find = |events: &[Event], name: &str| events.iter().find(|e| e.event.to_string() == name).map(|e| e.time);
+        let separation = find(rocket.events(), "separation(booster)").unwrap();
+        assert_approx_eq!(separation, burn_time);
+        assert_approx_eq!(find(rocket.events(), "ignition(second)").unwrap(), burn_time + 0.5);
+
+        // the booster flies (and lands) on its own, without the upper stage's motor
+        assert_eq!(rocket.boosters().len(), 1);
+        let booster = &rocket.boosters()[0];
+        assert_eq!(find(booster.events(), "separation(booster)"), Some(separation));
+        assert!(find(booster.events(), "deploy(booster_streamer)").is_some());
+        assert!(find(booster.events(), "ignition(second)").is_none());
+        assert!(booster.landed());
+        // the sustainer goes a lot higher, so it comes down later
+        assert!(find(rocket.events(), "landing").unwrap() > find(booster.events(), "landing").unwrap());
+    }
+
+    #[test]
+    fn ejection_charge() {
+        let f15 = MotorCatalog::builtin().find("F15").unwrap();
+        let fly = |delay: secs| {
+            let mut rocket = RocketFactory::with_mass(500.0)
+                .add_engine("main".into(), f15.with_delay(delay).unwrap().with_ejection_impulse(-0.5))
+                .drag(0.0025, 0.5)
+                .add_recovery(RecoveryDevice::parachute("chute", 0.6, 1.5).deploy_at(DeployTrigger::Ejection))
+                .integrator(IntegrationMethod::RK4)
+                .at(F64x3::zero());
+            rocket.light_engine("main".into());
+            while !rocket.landed() {
+                rocket.tick(0.01);
+                assert!(rocket.time() < 300.0);
+            }
+            rocket
+        };
+        let find = |rocket: &Rocket, name: &str| rocket.events().iter().find(|e| e.event.to_string() == name).map(|e| e.time);
+
+        // too short a delay comes out on the way up, going fast
+        let early = fly(0.0);
+        let ejection = &early.ejections()[0];
+        assert_eq!(find(&early, "deploy(chute)"), Some(ejection.time));
+        assert!(ejection.time < find(&early, "apogee").unwrap());
+        assert!(ejection.speed() > 20.0);
+
+        let late = fly(8.0);
+        let ejection = &late.ejections()[0];
+        assert_eq!(find(&late, "ejection(main)"), Some(ejection.time));
+        assert!(ejection.time > find(&late, "apogee").unwrap());
+    }
+
+    #[test]
+    fn cluster() {
+        let fly = |light: &[&str]| {
+            let e12 = MotorCatalog::builtin().find("E12").unwrap();
+            let mut rocket = RocketFactory::with_mass(1000.0)
+                .inertia(F64x3::new(0.05, 0.05, 0.002))
+                .add_engine_at("left".into(), e12, Mount::at(F64x3::new(-0.03, 0.0, -0.3)))
+                .add_engine_at("right".into(), e12, Mount::at(F64x3::new(0.03, 0.0, -0.3)))
+                .integrator(IntegrationMethod::RK4)
+                .at(F64x3::zero());
+            for name in light {
+                rocket.light_engine(name.to_string());
+            }
+            for _ in 0..50 {
+                rocket.tick(0.01);
+            }
+            rocket
+        };
+
+        // balanced, so it goes straight up
+        let both = fly(&["left", "right"]);
+        assert_approx_eq!(both.angular_velocity().norm(), 0.0);
+        assert_approx_eq!(both.position().x, 0.0);
+
+        // with only the left motor lit, the thrust is off to the side and the rocket turns towards +x
+        let left = fly(&["left"]);
+        assert!(left.angular_velocity().y > 1.0);
+        assert!(left.position().x > 0.0);
+        assert!(both.position().z > left.position().z);
+    }
 }
@@ -0,0 +1,135 @@
+//! lower stages of a multi stage rocket
+
+use std::collections::BTreeMap;
+
+use crate::aero::{Drag, DragCoefficient};
+use crate::events::{Event, FlightEvent};
+use crate::math::F64x3;
+use crate::motor::{Motor, Mount};
+use crate::recovery::RecoveryDevice;
+use crate::{secs, GRAMS_TO_KG};
+
+/// when a stage lets go of the rest of the rocket
+#[derive(Debug, Clone, PartialEq)]
+pub enum SeparationTrigger {
+    /// once every motor in the stage has burnt out
+    Burnout,
+    /// this many seconds after liftoff
+    Time(secs),
+    /// when an event happens (eg. apogee)
+    Event(FlightEvent),
+}
+
+/// a stage below the rest of the rocket, which is dropped once it has done its job
+///
+/// after separation it is simulated on its own, as a booster of the [`Rocket`](crate::rocket::Rocket) it came off
+#[derive(Debug, Clone)]
+pub struct Stage {
+    pub(crate) name: String,
+    /// grams, without the motors
+    pub(crate) mass: f64,
+    pub(crate) engines: BTreeMap<String, Motor>,
+    /// where the engines are, engines that are not in here are on the center line
+    pub(crate) mounts: BTreeMap<String, Mount>,
+    pub(crate) drag: Drag,
+    pub(crate) inertia: F64x3,
+    pub(crate) recovery: Vec<RecoveryDevice>,
+    pub(crate) separation: SeparationTrigger,
+    pub(crate) airstart_delay: Option<secs>,
+}
+
+impl Stage {
+    /// a stage with the mass in grams, separating at burnout
+    pub fn new(name: impl Into<String>, mass: f64) -> Self {
+        Self {
+            name: name.into(),
+            mass,
+            engines: BTreeMap::new(),
+            mounts: BTreeMap::new(),
+            drag: Drag::default(),
+            inertia: F64x3::zero(),
+            recovery: vec![],
+            separation: SeparationTrigger::Burnout,
+            airstart_delay: None,
+        }
+    }
+
+    pub fn add_engine(mut self, name: String, engine: impl Into<Motor>) -> Self {
+        self.engines.insert(name, engine.into());
+        self
+    }
+
+    /// adds an engine at a position (and direction) in the stage, for clusters
+    pub fn add_engine_at(mut self, name: String, engine: impl Into<Motor>, mount: Mount) -> Self {
+        self.mounts.insert(name.clone(), mount);
+        self.add_engine(name, engine)
+    }
+
+    /// reference area (m^2) and drag coefficient of the stage. the stack uses the drag of whichever attached stage is widest
+    pub fn drag(mut self, reference_area: f64, coefficient: impl Into<DragCoefficient>) -> Self {
+        self.drag = Drag::new(reference_area, coefficient);
+        self
+    }
+
+    /// principal moments of inertia (kg*m^2) of the stage on its own, once it has separated
+    pub fn inertia(mut self, inertia: F64x3) -> Self {
+        self.inertia = inertia;
+        self
+    }
+
+    /// recovery for the stage once it has separated
+    pub fn add_recovery(mut self, device: RecoveryDevice) -> Self {
+        self.recovery.push(device);
+        self
+    }
+
+    pub fn separate_on(mut self, trigger: SeparationTrigger) -> Self {
+        self.separation = trigger;
+        self
+    }
+
+    /// lights this stage's motors this long after the stage below it separates (an airstart)
+    pub fn airstart_delay(mut self, delay: secs) -> Self {
+        self.airstart_delay = Some(delay);
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub(crate) fn mount(&self, name: &str) -> Mount {
+        self.mounts.get(name).copied().unwrap_or_default()
+    }
+
+    /// mass of the stage (kg) at the given time
+    pub(crate) fn mass_at(&self, time: secs) -> f64 {
+        self.mass * GRAMS_TO_KG + self.engines.values().map(|e| e.weight_at(time)).sum::<f64>()
+    }
+
+    /// lights every motor in the stage at `time`
+    pub(crate) fn light(&mut self, time: secs) {
+        self.engines.values_mut().filter(|e| !e.lit()).for_each(|e| e.light(time));
+    }
+
+    /// the time the stage separates, if it does in a step ending at `time`
+    ///
+    /// `events` is every event of the flight so far, including the ones from this step
+    pub(crate) fn separation_time(&self, events: &[Event], time: secs) -> Option<secs> {
+        match &self.separation {
+            SeparationTrigger::Burnout => {
+                let mut burnout = 0.0f64;
+                for engine in self.engines.values() {
+                    burnout = burnout.max(engine.ignition_time()? + engine.burn_time());
+                }
+                (burnout <= time && !self.engines.is_empty()).then_some(burnout)
+            }
+            SeparationTrigger::Time(delay) => events
+                .iter()
+                .find(|e| e.event == FlightEvent::Liftoff)
+                .map(|liftoff| liftoff.time + delay)
+                .filter(|separation| *separation <= time),
+            SeparationTrigger::Event(wanted) => events.iter().find(|e| e.event == *wanted).map(|e| e.time),
+        }
+    }
+}
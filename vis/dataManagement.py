@@ -16,7 +16,8 @@ class dataVisualiser:
             descriptionNum += 1
             
         with open(self.file, newline='\n') as pathFile:
-            reader = csv.reader(pathFile, delimiter=',', quotechar='"')
+            # lines starting with # are comments (like the seed of the run)
+            reader = csv.reader((line for line in pathFile if not line.startswith('#')), delimiter=',', quotechar='"')
             logList = []
             dataOut = []
             for index, row in enumerate(reader):
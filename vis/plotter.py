@@ -25,7 +25,10 @@ class plotter:
     def read_header(self, file_name):
         # os.chdir('..')
         f = open(file_name, "r")
+        # skip the comments above the header (like the seed of the run)
         self.header = f.readline()
+        while self.header.startswith("#"):
+            self.header = f.readline()
         self.data_descriptions = [i.strip() for i in self.header.split(',')]
         self.viewer.allDataDescriptions = self.data_descriptions
 